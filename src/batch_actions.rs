@@ -0,0 +1,186 @@
+// batch_actions.rs - CSV 驱动的批量操作引擎
+// 读取一份 CSV 清单（每行一条“匹配模式 + 操作”规则），在内存索引中解析出匹配文件，
+// 对磁盘执行重命名/移动/删除/文件内查找替换。支持 dry_run 预览，不直接维护索引——
+// 操作落地后由已有的 USN 监控循环捕获 add/delete/modify 增量，自动把索引对齐到新状态
+
+use crate::search_index::IndexedItem;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// 清单里每一行解析出的操作类型
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActionOp {
+    /// 把匹配文件改名为给定文件名（同目录下）
+    Rename { new_name: String },
+    /// 把匹配文件移动到目标目录
+    MoveTo { dir: String },
+    /// 删除匹配文件
+    Delete,
+    /// 对匹配的文本文件做一次全文查找替换
+    Replace { find: String, replace: String },
+}
+
+/// 一条清单规则：用 `pattern` 在文件名中做包含匹配，命中后执行 `op`
+#[derive(Clone, Debug)]
+pub struct ActionRule {
+    pub pattern: String,
+    pub op: ActionOp,
+}
+
+/// 单个文件上单条规则的执行结果（或 dry-run 预览）
+#[derive(Clone, Debug, Serialize)]
+pub struct ActionResult {
+    pub path: String,
+    pub op: String,
+    pub detail: String,
+    pub applied: bool,
+    pub error: Option<String>,
+}
+
+/// 解析 CSV 清单：`pattern,operation,arg1[,arg2]`
+/// operation 为 rename/move/delete/replace 之一；任一行字段缺失或操作不认识时，
+/// 直接返回带行号的错误，整份清单都不会被解析出规则
+pub fn parse_manifest(csv: &str) -> Result<Vec<ActionRule>, String> {
+    let mut rules = Vec::new();
+
+    for (line_no, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line_no == 0 && looks_like_header(line) {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        if fields.len() < 2 {
+            return Err(format!("第 {} 行缺少字段: {}", line_no + 1, line));
+        }
+
+        let pattern = fields[0].to_string();
+        let op = match fields[1].to_ascii_lowercase().as_str() {
+            "rename" => {
+                let new_name = fields.get(2).ok_or_else(|| format!("第 {} 行 rename 缺少新文件名", line_no + 1))?;
+                ActionOp::Rename { new_name: new_name.to_string() }
+            }
+            "move" => {
+                let dir = fields.get(2).ok_or_else(|| format!("第 {} 行 move 缺少目标目录", line_no + 1))?;
+                ActionOp::MoveTo { dir: dir.to_string() }
+            }
+            "delete" => ActionOp::Delete,
+            "replace" => {
+                let find = fields.get(2).ok_or_else(|| format!("第 {} 行 replace 缺少查找内容", line_no + 1))?;
+                let replace = fields.get(3).ok_or_else(|| format!("第 {} 行 replace 缺少替换内容", line_no + 1))?;
+                ActionOp::Replace { find: find.to_string(), replace: replace.to_string() }
+            }
+            other => return Err(format!("第 {} 行未知操作: {}", line_no + 1, other)),
+        };
+
+        rules.push(ActionRule { pattern, op });
+    }
+
+    Ok(rules)
+}
+
+fn looks_like_header(first_line: &str) -> bool {
+    let lower = first_line.to_ascii_lowercase();
+    lower.starts_with("pattern,operation") || lower.starts_with("pattern,op")
+}
+
+/// 在给定的一批内存索引条目中解析出匹配某条规则的文件（按文件名包含匹配，忽略目录）
+fn resolve_matches<'a>(items: &'a [IndexedItem], pattern: &str) -> Vec<&'a IndexedItem> {
+    let pattern_lower = pattern.to_lowercase();
+    items
+        .iter()
+        .filter(|item| !item.is_dir && item.name_lower.contains(&pattern_lower))
+        .collect()
+}
+
+/// 对一批索引条目依次应用清单里的所有规则，返回每个匹配文件的执行（或预览）结果。
+/// `dry_run` 为 true 时只生成预览，不触碰磁盘；为 false 时真正执行，结果交给 USN 监控自行回收索引
+pub fn apply_manifest(items: &[IndexedItem], rules: &[ActionRule], dry_run: bool) -> Vec<ActionResult> {
+    let mut results = Vec::new();
+
+    for rule in rules {
+        for item in resolve_matches(items, &rule.pattern) {
+            results.push(apply_one(item, &rule.op, dry_run));
+        }
+    }
+
+    results
+}
+
+fn apply_one(item: &IndexedItem, op: &ActionOp, dry_run: bool) -> ActionResult {
+    let path = item.path.clone();
+
+    match op {
+        ActionOp::Rename { new_name } => {
+            let target = match Path::new(&path).parent() {
+                Some(parent) => parent.join(new_name).to_string_lossy().to_string(),
+                None => return error_result(&path, "rename", "无法解析父目录".to_string()),
+            };
+            let detail = format!("{} -> {}", path, target);
+            if dry_run {
+                return preview_result(&path, "rename", detail);
+            }
+            match fs::rename(&path, &target) {
+                Ok(()) => applied_result(&path, "rename", detail),
+                Err(e) => error_result(&path, "rename", e.to_string()),
+            }
+        }
+        ActionOp::MoveTo { dir } => {
+            let filename = match Path::new(&path).file_name() {
+                Some(name) => name,
+                None => return error_result(&path, "move", "无法解析文件名".to_string()),
+            };
+            let target = Path::new(dir).join(filename).to_string_lossy().to_string();
+            let detail = format!("{} -> {}", path, target);
+            if dry_run {
+                return preview_result(&path, "move", detail);
+            }
+            if let Err(e) = fs::create_dir_all(dir) {
+                return error_result(&path, "move", e.to_string());
+            }
+            match fs::rename(&path, &target) {
+                Ok(()) => applied_result(&path, "move", detail),
+                Err(e) => error_result(&path, "move", e.to_string()),
+            }
+        }
+        ActionOp::Delete => {
+            let detail = path.clone();
+            if dry_run {
+                return preview_result(&path, "delete", detail);
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => applied_result(&path, "delete", detail),
+                Err(e) => error_result(&path, "delete", e.to_string()),
+            }
+        }
+        ActionOp::Replace { find, replace } => {
+            let content = match fs::read_to_string(&path) {
+                Ok(c) => c,
+                Err(e) => return error_result(&path, "replace", e.to_string()),
+            };
+            let hits = content.matches(find.as_str()).count();
+            let detail = format!("{} 处匹配", hits);
+            if dry_run || hits == 0 {
+                return preview_result(&path, "replace", detail);
+            }
+            let updated = content.replace(find.as_str(), replace);
+            match fs::write(&path, updated) {
+                Ok(()) => applied_result(&path, "replace", detail),
+                Err(e) => error_result(&path, "replace", e.to_string()),
+            }
+        }
+    }
+}
+
+fn preview_result(path: &str, op: &str, detail: String) -> ActionResult {
+    ActionResult { path: path.to_string(), op: op.to_string(), detail, applied: false, error: None }
+}
+
+fn applied_result(path: &str, op: &str, detail: String) -> ActionResult {
+    ActionResult { path: path.to_string(), op: op.to_string(), detail, applied: true, error: None }
+}
+
+fn error_result(path: &str, op: &str, error: String) -> ActionResult {
+    ActionResult { path: path.to_string(), op: op.to_string(), detail: String::new(), applied: false, error: Some(error) }
+}
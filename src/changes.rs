@@ -0,0 +1,74 @@
+// changes.rs - 增量变化的发布/订阅系统
+// 替代原先写死的单个 Tauri `file-changes` 事件，允许 UI、内容重建索引、外部监听器等
+// 多个消费者各自订阅、各自消费、各自取消订阅，互不影响
+
+use parking_lot::RwLock;
+use std::sync::LazyLock;
+use tokio::sync::mpsc;
+
+/// 每个订阅者 channel 的容量，满了之后新到的通知对该订阅者直接丢弃（背压），不阻塞 USN 监控循环
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// 对应 USN `change.action` 码的语义化变化类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// action == 1
+    Added,
+    /// action == 2 或 3
+    Modified,
+    /// action == 0 或 4
+    Deleted,
+}
+
+impl ChangeKind {
+    /// 把 `FileChange::action` 的原始码映射为语义化的变化类型；未知码返回 None
+    pub fn from_action_code(action: u8) -> Option<Self> {
+        match action {
+            1 => Some(ChangeKind::Added),
+            2 | 3 => Some(ChangeKind::Modified),
+            0 | 4 => Some(ChangeKind::Deleted),
+            _ => None,
+        }
+    }
+}
+
+/// 一条增量索引变化
+#[derive(Clone, Debug)]
+pub struct IndexChange {
+    pub drive: char,
+    pub kind: ChangeKind,
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+    pub mtime: f64,
+}
+
+static SUBSCRIBERS: LazyLock<RwLock<Vec<mpsc::Sender<IndexChange>>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// 订阅增量变化。丢弃返回的 `Receiver` 即视为取消订阅，下一次 `publish` 会自动把它
+/// 从订阅列表中清理掉；监控循环停止时不会关闭订阅者的 channel
+pub fn subscribe_changes() -> mpsc::Receiver<IndexChange> {
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    SUBSCRIBERS.write().push(tx);
+    rx
+}
+
+/// 把一条变化广播给当前所有订阅者
+pub fn publish(change: IndexChange) {
+    let mut subscribers = SUBSCRIBERS.write();
+    subscribers.retain(|tx| {
+        match tx.try_send(change.clone()) {
+            Ok(()) => true,
+            // channel 已满但订阅者还在：丢弃这条通知而不是阻塞扫描循环（背压）
+            Err(mpsc::error::TrySendError::Full(_)) => true,
+            // 接收端已经被丢弃，说明订阅者已经不存在，清理掉
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    });
+}
+
+/// 当前订阅者数量，主要用于测试和诊断
+pub fn subscriber_count() -> usize {
+    SUBSCRIBERS.read().len()
+}
@@ -27,33 +27,199 @@ pub struct SearchResult {
     pub size: u64,
     pub mtime: u64,
     pub is_dir: bool,
+    /// MFT 文件引用号（非 NTFS 路径下是路径哈希），兼作 stat 语义里的 inode
+    pub inode: u64,
+    pub file_type: crate::search_index::FileType,
+    pub hard_links: u32,
 }
 
-/// 获取所有可用驱动器
+/// 驱动器类型（对应 `GetDriveTypeW` 的分类）
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum DriveType {
+    Fixed,
+    Removable,
+    Remote,
+    CdRom,
+    RamDisk,
+    Unknown,
+}
+
+/// 驱动器详细信息，供前端展示盘符类型/卷标/文件系统/容量，并按需排除网络盘/可移动盘
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DriveInfo {
+    pub path: String,
+    pub drive_type: DriveType,
+    pub volume_label: String,
+    /// 文件系统名称（如 "NTFS"、"FAT32"、"exFAT"），查询失败时为空字符串
+    pub filesystem: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub is_removable: bool,
+    pub is_network: bool,
+}
+
+/// 枚举实际挂载的卷（使用 `GetLogicalDriveStrings` + `GetDriveTypeW` 分类），
+/// 比暴力探测 'A'..='Z' 更快也更准确（能区分可移动盘/网络盘/光驱），
+/// 并顺带查询文件系统名称与容量供前端展示剩余空间
+#[cfg(target_os = "windows")]
+fn enumerate_drives_windows() -> Vec<DriveInfo> {
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetDiskFreeSpaceExW, GetDriveTypeW, GetLogicalDriveStringsW, GetVolumeInformationW,
+        DRIVE_CDROM, DRIVE_FIXED, DRIVE_RAMDISK, DRIVE_REMOTE, DRIVE_REMOVABLE,
+    };
+
+    let mut buf = [0u16; 1024];
+    let len = unsafe { GetLogicalDriveStringsW(buf.len() as u32, buf.as_mut_ptr()) };
+    if len == 0 {
+        return Vec::new();
+    }
+
+    let mut drives = Vec::new();
+    for root in buf[..len as usize].split(|&c| c == 0).filter(|s| !s.is_empty()) {
+        let path = String::from_utf16_lossy(root);
+        let root_nul: Vec<u16> = root.iter().copied().chain(std::iter::once(0)).collect();
+
+        let drive_type = match unsafe { GetDriveTypeW(root_nul.as_ptr()) } {
+            DRIVE_FIXED => DriveType::Fixed,
+            DRIVE_REMOVABLE => DriveType::Removable,
+            DRIVE_REMOTE => DriveType::Remote,
+            DRIVE_CDROM => DriveType::CdRom,
+            DRIVE_RAMDISK => DriveType::RamDisk,
+            _ => DriveType::Unknown,
+        };
+
+        let mut label_buf = [0u16; 256];
+        let mut fs_name_buf = [0u16; 32];
+        let volume_label;
+        let filesystem;
+        unsafe {
+            if GetVolumeInformationW(
+                root_nul.as_ptr(),
+                label_buf.as_mut_ptr(),
+                label_buf.len() as u32,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                fs_name_buf.as_mut_ptr(),
+                fs_name_buf.len() as u32,
+            ) != 0
+            {
+                let end = label_buf.iter().position(|&c| c == 0).unwrap_or(0);
+                volume_label = String::from_utf16_lossy(&label_buf[..end]);
+                let fs_end = fs_name_buf.iter().position(|&c| c == 0).unwrap_or(0);
+                filesystem = String::from_utf16_lossy(&fs_name_buf[..fs_end]);
+            } else {
+                volume_label = String::new();
+                filesystem = String::new();
+            }
+        }
+
+        let (mut total_bytes, mut available_bytes) = (0u64, 0u64);
+        unsafe {
+            GetDiskFreeSpaceExW(
+                root_nul.as_ptr(),
+                &mut available_bytes,
+                &mut total_bytes,
+                std::ptr::null_mut(),
+            );
+        }
+
+        drives.push(DriveInfo {
+            path,
+            is_removable: drive_type == DriveType::Removable,
+            is_network: drive_type == DriveType::Remote,
+            drive_type,
+            volume_label,
+            filesystem,
+            total_bytes,
+            available_bytes,
+        });
+    }
+
+    drives
+}
+
+/// 获取所有可用驱动器（仅路径，兼容旧调用方）
 #[tauri::command]
 pub async fn get_all_drives() -> Result<Vec<String>, String> {
     #[cfg(target_os = "windows")]
     {
-        use std::path::Path;
-        let drives = ('A'..='Z')
-            .filter_map(|letter| {
-                let drive = format!("{}:\\", letter);
-                if Path::new(&drive).exists() {
-                    Some(drive)
-                } else {
-                    None
-                }
-            })
-            .collect();
-        Ok(drives)
+        Ok(enumerate_drives_windows().into_iter().map(|d| d.path).collect())
     }
-    
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        Ok(enumerate_drives_generic().into_iter().map(|d| d.path).collect())
+    }
+}
+
+/// 获取带类型/卷标的驱动器详情，供前端展示与筛选（例如排除网络盘）
+#[tauri::command]
+pub async fn get_drives_detail() -> Result<Vec<DriveInfo>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        Ok(enumerate_drives_windows())
+    }
+
     #[cfg(not(target_os = "windows"))]
     {
-        Ok(vec![String::from("/")])
+        Ok(enumerate_drives_generic())
     }
 }
 
+/// 文件系统名称是否对应网络挂载（NFS/CIFS/SMB 等），用于让前端默认跳过网络盘
+#[cfg(not(target_os = "windows"))]
+fn is_network_filesystem(filesystem: &str) -> bool {
+    matches!(
+        filesystem.to_ascii_lowercase().as_str(),
+        "nfs" | "nfs4" | "cifs" | "smb" | "smbfs" | "afpfs" | "fuse.sshfs"
+    )
+}
+
+/// 枚举 Linux/macOS 下实际挂载的卷（基于 `sysinfo`），返回文件系统/容量/可移动/网络标记，
+/// 取代旧版硬编码的单一 `/` 根目录
+#[cfg(not(target_os = "windows"))]
+fn enumerate_drives_generic() -> Vec<DriveInfo> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+
+    disks
+        .iter()
+        .map(|disk| {
+            let filesystem = disk.file_system().to_string_lossy().to_string();
+            DriveInfo {
+                path: disk.mount_point().to_string_lossy().to_string(),
+                drive_type: if disk.is_removable() {
+                    DriveType::Removable
+                } else {
+                    DriveType::Fixed
+                },
+                volume_label: disk.name().to_string_lossy().to_string(),
+                is_network: is_network_filesystem(&filesystem),
+                is_removable: disk.is_removable(),
+                filesystem,
+                total_bytes: disk.total_space(),
+                available_bytes: disk.available_space(),
+            }
+        })
+        .collect()
+}
+
+/// 预加载默认应索引的驱动器：固定盘 + 可移动盘，跳过网络盘/光驱（慢且可能挂起）
+#[cfg(target_os = "windows")]
+pub fn preload_drive_letters() -> Vec<char> {
+    enumerate_drives_windows()
+        .into_iter()
+        .filter(|d| matches!(d.drive_type, DriveType::Fixed | DriveType::Removable))
+        .filter_map(|d| d.path.chars().next())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn preload_drive_letters() -> Vec<char> {
+    Vec::new()
+}
+
 /// 搜索文件（使用lib.rs中的搜索索引，支持流式输出）
 #[tauri::command]
 pub async fn search_files(
@@ -62,9 +228,9 @@ pub async fn search_files(
     scope: Option<String>,
 ) -> Result<Vec<SearchResult>, String> {
     log::info!("🔍 搜索: query='{}', scope={:?}", query, scope);
-    
+
     // 解析增强语法
-    let (pure_keyword, filters) = SearchSyntaxParser::parse(&query);
+    let (pure_keyword, filters) = SearchSyntaxParser::parse(&query)?;
     log::info!("📝 解析结果: 关键词='{}', 过滤器={:?}", pure_keyword, filters);
     
     // 确定要搜索的驱动器
@@ -167,6 +333,9 @@ pub async fn search_files(
                         size: item.size,
                         mtime: item.mtime as u64,
                         is_dir: item.is_dir,
+                        inode: item.file_ref,
+                        file_type: item.file_type,
+                        hard_links: item.hard_links,
                     }
                 }).collect();
                 
@@ -217,12 +386,13 @@ pub async fn realtime_search(
     scope: Option<String>,
 ) -> Result<Vec<SearchResult>, String> {
     use walkdir::WalkDir;
+    use std::os::windows::fs::MetadataExt;
     use std::time::SystemTime;
 
     log::info!("🔍 实时搜索: query='{}', scope={:?}", query, scope);
-    
+
     // 解析增强语法
-    let (pure_keyword, filters) = SearchSyntaxParser::parse(&query);
+    let (pure_keyword, filters) = SearchSyntaxParser::parse(&query)?;
     log::info!("📝 解析结果: 关键词='{}', 过滤器={:?}", pure_keyword, filters);
     
     let keyword = pure_keyword.to_lowercase();
@@ -299,6 +469,9 @@ pub async fn realtime_search(
                 size: metadata.len(),
                 mtime,
                 is_dir: metadata.is_dir(),
+                inode: metadata.file_index().unwrap_or(0),
+                file_type: crate::search_index::FileType::classify(metadata.is_dir(), entry.path_is_symlink()),
+                hard_links: metadata.number_of_links().unwrap_or(1),
             };
             
             // 应用过滤器
@@ -364,6 +537,11 @@ fn match_filters(item: &SearchResult, filters: &SearchFilters) -> bool {
             return false;
         }
     }
+    if let Some(date_before) = filters.date_before {
+        if item.mtime >= date_before {
+            return false;
+        }
+    }
 
     // 路径过滤
     if !filters.path.is_empty() {
@@ -374,15 +552,34 @@ fn match_filters(item: &SearchResult, filters: &SearchFilters) -> bool {
         }
     }
 
-    // 文件名模式过滤
-    if !filters.name_pattern.is_empty() {
-        let pattern = filters.name_pattern.to_lowercase();
-        let filename_lower = item.filename.to_lowercase();
-        if !filename_lower.contains(&pattern) {
+    // 文件名模式过滤（子串/glob/regex，见 `NameMatcher`）
+    if !filters.name_matcher.matches(&item.filename) {
+        return false;
+    }
+
+    // 文件种类过滤
+    if let Some(kind) = filters.kind {
+        if item.file_type != kind {
+            return false;
+        }
+    }
+
+    // type:exe —— 扩展名为 .exe 的文件
+    if filters.only_exe {
+        let ext = std::path::Path::new(&item.filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+        if item.is_dir || !ext.eq_ignore_ascii_case("exe") {
             return false;
         }
     }
 
+    // type:empty —— 大小为 0 的文件
+    if filters.only_empty && (item.is_dir || item.size != 0) {
+        return false;
+    }
+
     true
 }
 
@@ -462,6 +659,455 @@ pub async fn build_index(
     Ok("索引重建已在后台启动，请稍候...".to_string())
 }
 
+/// 在指定范围内查找重复文件，直接复用内存索引（不重新遍历磁盘）；扫描过程中通过
+/// `duplicate-scan-progress` 事件流式汇报进度，可用 `cancel_duplicate_scan` 中途取消
+#[tauri::command]
+pub async fn find_duplicate_files(
+    window: tauri::Window,
+    scope: Option<String>,
+    ignore_zero_length: Option<bool>,
+    ext_filter: Option<Vec<String>>,
+    path_prefix: Option<String>,
+) -> Result<Vec<crate::duplicates::DuplicateGroup>, String> {
+    let drives = if let Some(scope_str) = scope {
+        if scope_str == "all" || scope_str.is_empty() {
+            get_all_drives().await?
+        } else {
+            vec![scope_str]
+        }
+    } else {
+        get_all_drives().await?
+    };
+
+    let options = crate::duplicates::DuplicateOptions {
+        ignore_zero_length: ignore_zero_length.unwrap_or(true),
+        ext_filter,
+        path_prefix,
+        ..Default::default()
+    };
+
+    let mut all_groups = Vec::new();
+    let indices = SEARCH_INDICES.read();
+    for drive in &drives {
+        let drive_char = match drive.chars().next() {
+            Some(c) => c.to_ascii_uppercase(),
+            None => continue,
+        };
+        if let Some(index) = indices.get(&drive_char) {
+            let mut on_progress = |progress: crate::duplicates::DuplicateScanProgress| {
+                let _ = window.emit(
+                    "duplicate-scan-progress",
+                    serde_json::json!({
+                        "drive": drive_char.to_string(),
+                        "stage": progress.stage,
+                        "processed": progress.processed,
+                        "total": progress.total,
+                    }),
+                );
+            };
+            match crate::duplicates::find_duplicates_for_drive(
+                drive_char,
+                index,
+                &options,
+                &mut on_progress,
+            ) {
+                Ok(groups) => all_groups.extend(groups),
+                Err(e) => log::warn!("⚠️ {} 盘重复文件扫描跳过: {}", drive_char, e),
+            }
+        }
+    }
+
+    Ok(all_groups)
+}
+
+/// 取消指定驱动器正在进行的重复文件扫描；若没有扫描在跑则什么也不做
+#[tauri::command]
+pub async fn cancel_duplicate_scan(drive: String) -> Result<(), String> {
+    let drive_char = drive.chars().next().ok_or("Invalid drive")?.to_ascii_uppercase();
+    crate::duplicates::cancel_scan(drive_char);
+    Ok(())
+}
+
+/// 走 `database::Database` 这份 SQLite 影子索引的只读查询：用 `open_reader` 的只读连接，
+/// 跟常驻索引构建时台后的 `sync_drive`/`insert_batch` 写入并发，不互相阻塞。
+/// `mode` 对应 `database::MatchMode`：`exact`/`prefix`（默认）/`glob`/`fts`
+#[tauri::command]
+pub async fn search_files_db(
+    drive: String,
+    pattern: String,
+    mode: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<SearchResult>, String> {
+    let drive_char = drive.chars().next().ok_or("Invalid drive")?.to_ascii_uppercase();
+    let db_path = crate::database_path_for(drive_char);
+
+    let match_mode = match mode.as_deref() {
+        Some("exact") => crate::database::MatchMode::Exact,
+        Some("glob") => crate::database::MatchMode::Glob,
+        Some("fts") => crate::database::MatchMode::Fts,
+        _ => crate::database::MatchMode::Prefix,
+    };
+    let query = crate::database::Query {
+        pattern,
+        mode: match_mode,
+        limit: limit.unwrap_or(1000),
+        offset: offset.unwrap_or(0),
+        ..Default::default()
+    };
+
+    let conn = crate::database::Database::open_reader(&db_path).map_err(|e| e.to_string())?;
+    let db = crate::database::Database::from_connection(conn);
+    let entries = db.search(&query).map_err(|e| e.to_string())?;
+
+    Ok(entries
+        .into_iter()
+        .map(|e| SearchResult {
+            filename: e.name,
+            fullpath: e.full_path,
+            size: e.size,
+            mtime: e.mtime as u64,
+            is_dir: e.file_type.is_dir(),
+            inode: 0,
+            file_type: crate::search_index::FileType::classify(e.file_type.is_dir(), false),
+            hard_links: 1,
+        })
+        .collect())
+}
+
+/// 组合条件搜索：在指定范围内按名称 + 扩展名集合 + 大小范围 + 修改时间窗口联合过滤内存索引，
+/// 不重新遍历磁盘，支持"本周修改过的、大于 100MB 的视频"这类查询
+#[tauri::command]
+pub async fn search_filtered_files(
+    scope: Option<String>,
+    name_contains: Option<String>,
+    extensions: Option<Vec<String>>,
+    size_min: Option<u64>,
+    size_max: Option<u64>,
+    mtime_min: Option<f64>,
+    mtime_max: Option<f64>,
+    max_results: Option<usize>,
+) -> Result<Vec<crate::search_index::IndexedItem>, String> {
+    let drives = if let Some(scope_str) = scope {
+        if scope_str == "all" || scope_str.is_empty() {
+            get_all_drives().await?
+        } else {
+            vec![scope_str]
+        }
+    } else {
+        get_all_drives().await?
+    };
+
+    let query = crate::search_index::FilterQuery {
+        name_contains,
+        extensions: extensions.unwrap_or_default(),
+        size_min,
+        size_max,
+        mtime_min,
+        mtime_max,
+        file_type: None,
+        is_dir: None,
+        attrs_include: 0,
+        attrs_exclude: 0,
+    };
+    let max_results = max_results.unwrap_or(500);
+
+    let mut all_results = Vec::new();
+    let indices = SEARCH_INDICES.read();
+    for drive in &drives {
+        let drive_char = match drive.chars().next() {
+            Some(c) => c.to_ascii_uppercase(),
+            None => continue,
+        };
+        if let Some(index) = indices.get(&drive_char) {
+            all_results.extend(index.search_filtered(&query, max_results));
+            if all_results.len() >= max_results {
+                all_results.truncate(max_results);
+                break;
+            }
+        }
+    }
+
+    Ok(all_results)
+}
+
+/// 拼写容错搜索：查询词允许有限次编辑距离内的拼写偏差（如 "documnet" 命中 "document"）。
+/// 每个驱动器内部已按编辑距离升序返回，这里只按驱动器顺序聚合并截断总数
+#[tauri::command]
+pub async fn search_fuzzy_files(
+    query: String,
+    scope: Option<String>,
+    max_edits: Option<u8>,
+    max_results: Option<usize>,
+) -> Result<Vec<crate::search_index::IndexedItem>, String> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let drives = if let Some(scope_str) = scope {
+        if scope_str == "all" || scope_str.is_empty() {
+            get_all_drives().await?
+        } else {
+            vec![scope_str]
+        }
+    } else {
+        get_all_drives().await?
+    };
+
+    let max_edits = max_edits.unwrap_or(2);
+    let max_results = max_results.unwrap_or(100);
+
+    let mut all_results = Vec::new();
+    let indices = SEARCH_INDICES.read();
+    for drive in &drives {
+        let drive_char = match drive.chars().next() {
+            Some(c) => c.to_ascii_uppercase(),
+            None => continue,
+        };
+        if let Some(index) = indices.get(&drive_char) {
+            all_results.extend(index.search_fuzzy(&query, max_edits, max_results));
+        }
+    }
+
+    all_results.truncate(max_results);
+    Ok(all_results)
+}
+
+/// 多词 AND 搜索：按空格/分隔符/camelCase 把查询串分词，命中要求文件名包含全部词项（顺序不限），
+/// 例如 "project final" 能命中 "final_project_report.docx"
+#[tauri::command]
+pub async fn search_terms(
+    query: String,
+    scope: Option<String>,
+    max_results: Option<usize>,
+) -> Result<Vec<crate::search_index::IndexedItem>, String> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let drives = if let Some(scope_str) = scope {
+        if scope_str == "all" || scope_str.is_empty() {
+            get_all_drives().await?
+        } else {
+            vec![scope_str]
+        }
+    } else {
+        get_all_drives().await?
+    };
+
+    let max_results = max_results.unwrap_or(200);
+
+    let mut all_results = Vec::new();
+    let indices = SEARCH_INDICES.read();
+    for drive in &drives {
+        let drive_char = match drive.chars().next() {
+            Some(c) => c.to_ascii_uppercase(),
+            None => continue,
+        };
+        if let Some(index) = indices.get(&drive_char) {
+            all_results.extend(index.search_terms(&query, max_results));
+        }
+    }
+
+    all_results.truncate(max_results);
+    Ok(all_results)
+}
+
+/// 目录子树内搜索：`dir_path` 决定了所在驱动器和子树范围，`query` 为空时返回整个子树
+#[tauri::command]
+pub async fn search_in_dir(
+    dir_path: String,
+    query: Option<String>,
+    max_results: Option<usize>,
+) -> Result<Vec<crate::search_index::IndexedItem>, String> {
+    let drive_char = dir_path
+        .chars()
+        .next()
+        .map(|c| c.to_ascii_uppercase())
+        .ok_or_else(|| "dir_path 为空".to_string())?;
+
+    let query = query.unwrap_or_default();
+    let max_results = max_results.unwrap_or(500);
+
+    let indices = SEARCH_INDICES.read();
+    let index = match indices.get(&drive_char) {
+        Some(index) => index,
+        None => return Ok(Vec::new()),
+    };
+
+    Ok(index.search_in_dir(&dir_path, &query, max_results))
+}
+
+/// 类 grep 的全文内容搜索：只覆盖 USN 增量管道已经分词过的文本文件（见 `content_index` 允许列表）
+#[tauri::command]
+pub async fn search_content(
+    query: String,
+    max_results: Option<usize>,
+) -> Result<Vec<crate::content_index::ContentHit>, String> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(crate::content_index::search_content(
+        &query,
+        max_results.unwrap_or(200),
+    ))
+}
+
+/// grep 式全文内容搜索：和 `realtime_search` 并列的另一条搜索路径——不依赖 `content_index` 的
+/// 预建倒排索引（那条路径只覆盖 USN 增量管道已经分词过的文本文件），而是现读候选文件内容逐行扫描，
+/// 返回命中行号和一小段摘要。`name_filter` 复用 `SearchSyntaxParser` 的 `ext:`/`size:`/`dm:`/`path:`
+/// 等过滤语法先在文件名索引里筛一遍候选文件，避免对整棵树的每个文件都读一遍；`pattern` 才是内容层面
+/// 的查找词，`is_regex` 为 true 时按正则匹配（大小写不敏感），否则按字面量做子串扫描
+#[tauri::command]
+pub async fn grep_content_search(
+    name_filter: Option<String>,
+    pattern: String,
+    is_regex: Option<bool>,
+    scope: Option<String>,
+    max_candidates: Option<usize>,
+    max_matches_per_file: Option<usize>,
+    max_results: Option<usize>,
+) -> Result<Vec<crate::content_index::ContentMatch>, String> {
+    if pattern.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (pure_keyword, filters) = SearchSyntaxParser::parse(&name_filter.unwrap_or_default())?;
+    let keyword = pure_keyword.to_lowercase();
+    let max_candidates = max_candidates.unwrap_or(5000);
+
+    let drives = if let Some(scope_str) = scope {
+        if scope_str == "all" || scope_str.is_empty() {
+            get_all_drives().await?
+        } else {
+            vec![scope_str]
+        }
+    } else {
+        get_all_drives().await?
+    };
+
+    let mut candidates = Vec::new();
+    let indices = SEARCH_INDICES.read();
+    for drive in &drives {
+        let drive_char = match drive.chars().next() {
+            Some(c) => c.to_ascii_uppercase(),
+            None => continue,
+        };
+        let index = match indices.get(&drive_char) {
+            Some(idx) => idx,
+            None => continue,
+        };
+
+        let items = if keyword.is_empty() {
+            index.search_contains("", max_candidates)
+        } else {
+            index.search_contains(&keyword, max_candidates)
+        };
+
+        let drive_results: Vec<SearchResult> = items
+            .into_iter()
+            .filter(|item| !item.is_dir)
+            .map(|item| SearchResult {
+                filename: item.name,
+                fullpath: item.path,
+                size: item.size,
+                mtime: item.mtime as u64,
+                is_dir: item.is_dir,
+                inode: item.file_ref,
+                file_type: item.file_type,
+                hard_links: item.hard_links,
+            })
+            .collect();
+
+        let drive_results = SearchSyntaxParser::apply_filters(drive_results, &filters);
+        candidates.extend(drive_results.into_iter().map(|r| r.fullpath));
+        if candidates.len() >= max_candidates {
+            candidates.truncate(max_candidates);
+            break;
+        }
+    }
+    drop(indices);
+
+    crate::content_index::grep_search(
+        &candidates,
+        &pattern,
+        is_regex.unwrap_or(false),
+        max_matches_per_file.unwrap_or(20),
+        max_results.unwrap_or(500),
+    )
+}
+
+/// CSV 清单驱动的批量操作：每行一条"匹配模式 + 操作（rename/move/delete/replace）"规则，
+/// 在指定范围的内存索引里解析出匹配文件并执行。dry_run 为 true 时只返回预览，不触碰磁盘；
+/// 真正执行后不在这里维护索引，交给 start_file_monitoring 的 USN 增量管道自动回收
+#[tauri::command]
+pub async fn run_batch_actions(
+    manifest_csv: String,
+    scope: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<Vec<crate::batch_actions::ActionResult>, String> {
+    let rules = crate::batch_actions::parse_manifest(&manifest_csv)?;
+    let dry_run = dry_run.unwrap_or(true);
+
+    let drives = if let Some(scope_str) = scope {
+        if scope_str == "all" || scope_str.is_empty() {
+            get_all_drives().await?
+        } else {
+            vec![scope_str]
+        }
+    } else {
+        get_all_drives().await?
+    };
+
+    let mut all_results = Vec::new();
+    let indices = SEARCH_INDICES.read();
+    for drive in &drives {
+        let drive_char = match drive.chars().next() {
+            Some(c) => c.to_ascii_uppercase(),
+            None => continue,
+        };
+        if let Some(index) = indices.get(&drive_char) {
+            let items = index.all_items();
+            all_results.extend(crate::batch_actions::apply_manifest(&items, &rules, dry_run));
+        }
+    }
+
+    Ok(all_results)
+}
+
+/// 清除持久化索引并立即重建，用于索引损坏或校验 token 不匹配时手动恢复
+#[tauri::command]
+pub async fn clear_index(scope: Option<String>) -> Result<String, String> {
+    let drives = if let Some(scope_str) = scope {
+        if scope_str == "all" || scope_str.is_empty() {
+            get_all_drives().await?
+        } else {
+            vec![scope_str]
+        }
+    } else {
+        get_all_drives().await?
+    };
+
+    std::thread::spawn(move || {
+        for drive in &drives {
+            let drive_char = match drive.chars().next() {
+                Some(c) => c.to_ascii_uppercase(),
+                None => continue,
+            };
+
+            log::info!("🧹 清除 {} 盘持久化索引并重建...", drive_char);
+            if crate::force_rebuild_search_index_internal(drive_char) {
+                log::info!("✅ {} 盘索引已清除并重建", drive_char);
+            } else {
+                log::warn!("⚠️ {} 盘索引重建失败", drive_char);
+            }
+        }
+    });
+
+    Ok("索引清除与重建已在后台启动".to_string())
+}
+
 /// 检查索引状态
 #[tauri::command]
 pub async fn check_index_status(scope: Option<String>) -> Result<serde_json::Value, String> {
@@ -563,17 +1209,68 @@ pub async fn locate_file(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Windows 下用 `SHFileOperationW` 把文件/目录移进回收站，而不是拼 PowerShell 脚本
+/// （拼出来的 `Remove-Item -Path '{}'` 对带引号的路径是 shell 注入隐患，而且直接永久删除）
+#[cfg(target_os = "windows")]
+fn move_to_recycle_bin(path: &str) -> Result<(), String> {
+    use windows_sys::Win32::UI::Shell::{SHFileOperationW, FOF_ALLOWUNDO, FOF_NOCONFIRMATION, FO_DELETE, SHFILEOPSTRUCTW};
+
+    // pFrom 要求以双 NUL 结尾的路径列表
+    let mut from: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+    from.push(0);
+
+    let mut op = SHFILEOPSTRUCTW {
+        hwnd: 0,
+        wFunc: FO_DELETE,
+        pFrom: from.as_ptr(),
+        pTo: std::ptr::null(),
+        fFlags: (FOF_ALLOWUNDO | FOF_NOCONFIRMATION) as u16,
+        fAnyOperationsAborted: 0,
+        hNameMappings: std::ptr::null_mut(),
+        lpszProgressTitle: std::ptr::null(),
+    };
+
+    let result = unsafe { SHFileOperationW(&mut op) };
+    if result != 0 || op.fAnyOperationsAborted != 0 {
+        return Err(format!("移动到回收站失败，错误码: {}", result));
+    }
+
+    Ok(())
+}
+
+/// 删除文件/目录：默认走回收站（Windows 上 `SHFileOperationW`，macOS/Linux 上系统回收站 API），
+/// `permanent` 为 true 时才真正永久删除，跳过回收站
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<(), String> {
-    // 先从索引中移除（使用路径查找）
+pub async fn delete_file(window: tauri::Window, path: String, permanent: Option<bool>) -> Result<(), String> {
+    let permanent = permanent.unwrap_or(false);
+
+    if permanent {
+        let metadata = std::fs::metadata(&path).map_err(|e| e.to_string())?;
+        if metadata.is_dir() {
+            std::fs::remove_dir_all(&path).map_err(|e| e.to_string())?;
+        } else {
+            std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+        }
+    } else {
+        #[cfg(target_os = "windows")]
+        {
+            move_to_recycle_bin(&path)?;
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            trash::delete(&path).map_err(|e| e.to_string())?;
+        }
+    }
+
+    // 从索引中移除（使用路径查找），再保存索引到磁盘
     if let Some(drive_char) = path.chars().next() {
         let drive = drive_char.to_ascii_uppercase();
         let indices = SEARCH_INDICES.read();
         if let Some(index) = indices.get(&drive) {
             if index.remove_file_by_path(&path) {
                 log::info!("🗑️ 从索引中删除: {}", path);
-                
-                // 保存索引到磁盘
+
                 let index_path = format!("{}:\\.search_index.bin", drive);
                 let _ = index.save_to_file(std::path::Path::new(&index_path));
             } else {
@@ -581,21 +1278,28 @@ pub async fn delete_file(path: String) -> Result<(), String> {
             }
         }
     }
-    
-    // 再删除文件系统中的文件
-    #[cfg(target_os = "windows")]
-    {
-        let ps_script = format!("Remove-Item -Path '{}' -Force -Recurse", path.replace("'", "''"));
-        std::process::Command::new("powershell")
-            .args(&["-NoProfile", "-Command", &ps_script])
-            .output()
-            .map_err(|e| e.to_string())?;
-    }
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        std::fs::remove_file(&path).map_err(|e| e.to_string())?;
-    }
+    let _ = window.emit("file-deleted", serde_json::json!({ "path": path, "permanent": permanent }));
+
+    Ok(())
+}
+
+/// 重命名/移动文件或目录，成功后发事件让前端刷新受影响的条目
+#[tauri::command]
+pub async fn rename_file(window: tauri::Window, old_path: String, new_path: String) -> Result<(), String> {
+    std::fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?;
+
+    let _ = window.emit("file-renamed", serde_json::json!({ "old_path": old_path, "new_path": new_path }));
+
+    Ok(())
+}
+
+/// 创建目录（含必要的父目录），成功后发事件让前端刷新受影响的条目
+#[tauri::command]
+pub async fn create_directory(window: tauri::Window, path: String) -> Result<(), String> {
+    std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+
+    let _ = window.emit("directory-created", serde_json::json!({ "path": path }));
 
     Ok(())
 }
@@ -661,6 +1365,131 @@ pub async fn export_csv(results: Vec<SearchResult>) -> Result<(), String> {
     Ok(())
 }
 
+/// 目录浏览中的一个条目
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DirEntryInfo {
+    pub name: String,
+    pub fullpath: String,
+    pub is_dir: bool,
+    pub is_file: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+    /// 仅目录有意义：直接子项数量
+    pub child_count: Option<usize>,
+}
+
+/// 文件/目录的详细元数据
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileMetadata {
+    pub size: u64,
+    pub created: u64,
+    pub modified: u64,
+    pub accessed: u64,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub readonly: bool,
+    pub attributes: String,
+}
+
+fn system_time_to_unix(time: std::io::Result<std::time::SystemTime>) -> u64 {
+    time.ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 浏览目录：返回排序后的子条目（目录在前，文件在后），供 UI 内置的迷你文件浏览器使用
+#[tauri::command]
+pub async fn browse_directory(path: String) -> Result<Vec<DirEntryInfo>, String> {
+    let read_dir = std::fs::read_dir(&path).map_err(|e| format!("无法读取目录 {}: {}", path, e))?;
+
+    let mut entries = Vec::new();
+    for entry in read_dir {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        let metadata = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let fullpath = entry.path().to_string_lossy().to_string();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let is_dir = metadata.is_dir();
+
+        let child_count = if is_dir {
+            std::fs::read_dir(&fullpath).ok().map(|d| d.count())
+        } else {
+            None
+        };
+
+        entries.push(DirEntryInfo {
+            name,
+            fullpath,
+            is_dir,
+            is_file: metadata.is_file(),
+            is_symlink: metadata.file_type().is_symlink(),
+            size: if is_dir { 0 } else { metadata.len() },
+            child_count,
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    Ok(entries)
+}
+
+/// 获取单个文件/目录的详细元数据，供 UI 在不借助资源管理器的情况下展示属性面板
+#[tauri::command]
+pub async fn get_file_metadata(path: String) -> Result<FileMetadata, String> {
+    let metadata = std::fs::symlink_metadata(&path).map_err(|e| format!("无法读取元数据 {}: {}", path, e))?;
+
+    let mut attr_parts = Vec::new();
+    if metadata.is_dir() {
+        attr_parts.push("目录");
+    } else {
+        attr_parts.push("文件");
+    }
+    if metadata.permissions().readonly() {
+        attr_parts.push("只读");
+    }
+    if metadata.file_type().is_symlink() {
+        attr_parts.push("符号链接");
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+        const FILE_ATTRIBUTE_ARCHIVE: u32 = 0x20;
+
+        let win_attrs = metadata.file_attributes();
+        if win_attrs & FILE_ATTRIBUTE_HIDDEN != 0 {
+            attr_parts.push("隐藏");
+        }
+        if win_attrs & FILE_ATTRIBUTE_SYSTEM != 0 {
+            attr_parts.push("系统");
+        }
+        if win_attrs & FILE_ATTRIBUTE_ARCHIVE != 0 {
+            attr_parts.push("存档");
+        }
+    }
+
+    Ok(FileMetadata {
+        size: if metadata.is_dir() { 0 } else { metadata.len() },
+        created: system_time_to_unix(metadata.created()),
+        modified: system_time_to_unix(metadata.modified()),
+        accessed: system_time_to_unix(metadata.accessed()),
+        is_dir: metadata.is_dir(),
+        is_symlink: metadata.file_type().is_symlink(),
+        readonly: metadata.permissions().readonly(),
+        attributes: attr_parts.join(", "),
+    })
+}
+
 #[tauri::command]
 pub async fn get_config(_key: String) -> Result<String, String> {
     // TODO: Implement config retrieval
@@ -675,7 +1504,7 @@ pub async fn set_config(_key: String, _value: String) -> Result<(), String> {
 
 /// 启动 USN 文件监控
 #[tauri::command]
-pub async fn start_file_monitoring(window: tauri::Window, drives: Vec<String>) -> Result<(), String> {
+pub async fn start_file_monitoring(_window: tauri::Window, drives: Vec<String>) -> Result<(), String> {
     use std::sync::Arc;
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::time::Duration;
@@ -692,25 +1521,49 @@ pub async fn start_file_monitoring(window: tauri::Window, drives: Vec<String>) -
     // 为每个驱动器启动监控
     for drive_str in drives {
         let drive_char = drive_str.chars().next().ok_or("Invalid drive")?.to_ascii_uppercase();
-        let window_clone = window.clone();
         let stop_flag_clone = stop_flag.clone();
         
         // 在后台线程中监控
         tokio::spawn(async move {
             let mut last_usn = crate::get_current_usn(drive_char as u16);
-            
+            let mut last_journal_id = crate::get_usn_journal_id(drive_char as u16);
+
             log::info!("📊 {} 盘初始 USN: {}", drive_char, last_usn);
-            
+
             while !stop_flag_clone.load(Ordering::Relaxed) {
                 tokio::time::sleep(Duration::from_secs(2)).await;
-                
+
+                // Journal ID 变化说明日志被系统重新创建（例如卷被格式化/journal 被删除重建），
+                // 旧的 USN 游标不再有效，必须触发一次全量重建而不是继续增量拉取
+                let current_journal_id = crate::get_usn_journal_id(drive_char as u16);
+                if current_journal_id != 0 && current_journal_id != last_journal_id {
+                    log::warn!("⚠️ {} 盘 USN Journal 已重建 ({} -> {})，触发全量重建索引", drive_char, last_journal_id, current_journal_id);
+                    if crate::force_rebuild_search_index_internal(drive_char) {
+                        last_journal_id = current_journal_id;
+                        last_usn = crate::get_current_usn(drive_char as u16);
+                    }
+                    continue;
+                }
+
                 // 获取当前 USN 并检查变化
                 let current_usn = crate::get_current_usn(drive_char as u16);
-                
+
                 if current_usn > last_usn {
                     // 获取变化详情
                     let changes = crate::get_usn_changes(drive_char as u16, last_usn);
-                    
+
+                    // journal 在两次轮询之间被系统回收/重建，旧游标已失效，
+                    // 增量读取已经不可能追上了，和 journal_id 变化一样触发全量重建
+                    if changes.journal_invalid != 0 {
+                        log::warn!("⚠️ {} 盘 USN Journal 游标已失效，触发全量重建索引", drive_char);
+                        crate::free_change_list(changes);
+                        if crate::force_rebuild_search_index_internal(drive_char) {
+                            last_journal_id = crate::get_usn_journal_id(drive_char as u16);
+                            last_usn = crate::get_current_usn(drive_char as u16);
+                        }
+                        continue;
+                    }
+
                     let change_count = changes.count as i32;
                     if change_count > 0 {
                         log::info!("📁 {} 盘检测到 {} 个文件变化", drive_char, change_count);
@@ -726,8 +1579,8 @@ pub async fn start_file_monitoring(window: tauri::Window, drives: Vec<String>) -
                                 std::slice::from_raw_parts(changes.changes, changes.count)
                             };
                             
-                            let indices = crate::SEARCH_INDICES.read();
-                            if let Some(index) = indices.get(&drive_char) {
+                            let has_index = crate::SEARCH_INDICES.read().contains_key(&drive_char);
+                            if has_index {
                                 for change in changes_vec {
                                     // 获取路径
                                     let path = if change.path_ptr.is_null() {
@@ -738,17 +1591,31 @@ pub async fn start_file_monitoring(window: tauri::Window, drives: Vec<String>) -
                                         };
                                         String::from_utf8_lossy(path_bytes).to_string()
                                     };
-                                    
+
                                     if path.is_empty() {
                                         continue;
                                     }
-                                    
+
                                     // 0, 4 = 删除，1, 2, 3 = 添加/修改
                                     if change.action == 0 || change.action == 4 {
-                                        // 文件被删除 - 使用路径删除
-                                        if index.remove_file_by_path(&path) {
-                                            deleted_count += 1;
-                                            log::debug!("🗑️ 从索引删除: {}", path);
+                                        // 文件被删除 - 入队交给持久化 worker 异步应用，不在监控循环里同步改索引
+                                        crate::enqueue_persistence_update(
+                                            drive_char,
+                                            crate::persistence::IndexUpdate::RemoveByPath(path.clone()),
+                                        );
+                                        deleted_count += 1;
+                                        log::debug!("🗑️ 从索引删除: {}", path);
+                                        crate::content_index::purge_file(drive_char, &path);
+
+                                        if let Some(kind) = crate::changes::ChangeKind::from_action_code(change.action) {
+                                            crate::changes::publish(crate::changes::IndexChange {
+                                                drive: drive_char,
+                                                kind,
+                                                path: path.clone(),
+                                                size: 0,
+                                                is_dir: false,
+                                                mtime: 0.0,
+                                            });
                                         }
                                     } else if change.action == 1 || change.action == 2 || change.action == 3 {
                                         // 文件被添加或修改
@@ -759,36 +1626,72 @@ pub async fn start_file_monitoring(window: tauri::Window, drives: Vec<String>) -
                                                     .and_then(|n| n.to_str())
                                                     .unwrap_or("")
                                                     .to_string();
-                                                
+
                                                 // 使用路径哈希作为file_ref（与构建索引时不同，但用于增量添加）
                                                 let mut hasher = DefaultHasher::new();
                                                 path.hash(&mut hasher);
                                                 let file_ref = hasher.finish();
-                                                
+
                                                 let name_lower = filename.to_lowercase();
                                                 let parent_path = Path::new(&path).parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
                                                 let mut parent_hasher = DefaultHasher::new();
                                                 parent_path.hash(&mut parent_hasher);
                                                 let parent_ref = parent_hasher.finish();
-                                                
+
+                                                let size = metadata.len();
+                                                let is_dir = metadata.is_dir();
+                                                let attrs = {
+                                                    use std::os::windows::fs::MetadataExt;
+                                                    metadata.file_attributes()
+                                                };
+                                                let is_symlink = fs::symlink_metadata(&path)
+                                                    .map(|m| m.file_type().is_symlink())
+                                                    .unwrap_or(false);
+                                                let mtime = metadata.modified()
+                                                    .ok()
+                                                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                                                    .map(|d| d.as_secs_f64())
+                                                    .unwrap_or(0.0);
+
                                                 let item = crate::search_index::IndexedItem {
                                                     name: filename,
                                                     name_lower,
                                                     path: path.clone(),
                                                     file_ref,
                                                     parent_ref,
-                                                    size: metadata.len(),
-                                                    mtime: metadata.modified()
-                                                        .ok()
-                                                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                                                        .map(|d| d.as_secs_f64())
-                                                        .unwrap_or(0.0),
-                                                    is_dir: metadata.is_dir(),
+                                                    size,
+                                                    mtime,
+                                                    is_dir,
+                                                    extension: String::new(),
+                                                    file_type: crate::search_index::FileType::classify(is_dir, is_symlink),
+                                                    link_target: None,
+                                                    hard_links: 1,
+                                                    ctime: 0.0,
+                                                    atime: 0.0,
+                                                    ctime_raw: 0,
+                                                    atime_raw: 0,
+                                                    attrs,
                                                 };
-                                                
-                                                index.add_file(item);
+
+                                                // 入队交给持久化 worker 异步应用，保持监控循环自身不被磁盘 I/O 阻塞
+                                                crate::enqueue_persistence_update(
+                                                    drive_char,
+                                                    crate::persistence::IndexUpdate::Add(item),
+                                                );
                                                 added_count += 1;
                                                 log::debug!("📝 添加到索引: {}", path);
+                                                crate::content_index::index_file(drive_char, &path);
+
+                                                if let Some(kind) = crate::changes::ChangeKind::from_action_code(change.action) {
+                                                    crate::changes::publish(crate::changes::IndexChange {
+                                                        drive: drive_char,
+                                                        kind,
+                                                        path: path.clone(),
+                                                        size,
+                                                        is_dir,
+                                                        mtime,
+                                                    });
+                                                }
                                             }
                                         }
                                     }
@@ -797,16 +1700,10 @@ pub async fn start_file_monitoring(window: tauri::Window, drives: Vec<String>) -
                         }
                         
                         log::info!("📑 索引更新: +{} -{}", added_count, deleted_count);
-                        
-                        // NOTE: 前端已取消直接显示USN增量变化，此处不再发送file-changes事件
-                        // 后端仍然继续监控USN并更新索引（无声模式）
-                        // let _ = window_clone.emit("file-changes", serde_json::json!({
-                        //     "drive": drive_char.to_string(),
-                        //     "added": added_count,
-                        //     "deleted": deleted_count,
-                        //     "total": change_count
-                        // }));
-                        
+
+                        // 前端已取消直接显示 USN 增量变化，改为经 changes::publish 广播给
+                        // 各自订阅的消费者（UI、内容重索引、外部监听器），不再硬编码单个事件
+
                         // 释放内存
                         crate::free_change_list(changes);
                     }
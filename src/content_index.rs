@@ -0,0 +1,291 @@
+// content_index.rs - 可选的全文内容索引，由 USN 增量管道驱动
+// 只对扩展名在允许列表内的文本文件分词建立倒排索引，增量 add/delete 跟随 USN action 同步，
+// 不需要也不做一次性全盘扫描（内容足够小、数量足够少，靠增量管道喂数据即可）
+
+use parking_lot::RwLock;
+use regex::{Regex, RegexBuilder};
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// 参与内容索引的扩展名（小写，含前导 `.`）
+pub const CONTENT_INDEX_EXTS: &[&str] = &[".txt", ".md", ".rs", ".csv", ".log", ".json", ".toml"];
+
+/// 单次内容分词命中：所在行号（从 1 开始）与行内字节偏移
+type Hit = (usize, usize);
+
+/// 一条内容搜索命中结果
+#[derive(Clone, Debug, Serialize)]
+pub struct ContentHit {
+    pub drive: char,
+    pub path: String,
+    pub line: usize,
+    pub offset: usize,
+}
+
+/// 单个驱动器的内容倒排索引：token -> path -> 该文件内的命中列表
+#[derive(Default)]
+struct ContentIndex {
+    inverted: FxHashMap<String, FxHashMap<String, Vec<Hit>>>,
+    /// path -> 该文件贡献过的 token 列表，删除/刷新时用来精准清理 `inverted`
+    path_tokens: FxHashMap<String, Vec<String>>,
+}
+
+impl ContentIndex {
+    fn purge_path(&mut self, path: &str) {
+        if let Some(tokens) = self.path_tokens.remove(path) {
+            for token in tokens {
+                if let Some(paths) = self.inverted.get_mut(&token) {
+                    paths.remove(path);
+                    if paths.is_empty() {
+                        self.inverted.remove(&token);
+                    }
+                }
+            }
+        }
+    }
+
+    fn index_path(&mut self, path: &str, tokens: FxHashMap<String, Vec<Hit>>) {
+        self.purge_path(path);
+
+        let mut token_list = Vec::with_capacity(tokens.len());
+        for (token, hits) in tokens {
+            self.inverted
+                .entry(token.clone())
+                .or_default()
+                .insert(path.to_string(), hits);
+            token_list.push(token);
+        }
+        self.path_tokens.insert(path.to_string(), token_list);
+    }
+}
+
+static INDICES: LazyLock<RwLock<FxHashMap<char, ContentIndex>>> =
+    LazyLock::new(|| RwLock::new(FxHashMap::default()));
+
+/// 判断某个路径的扩展名是否在内容索引允许列表中
+pub fn is_content_indexable(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e.to_ascii_lowercase()))
+        .is_some_and(|ext| CONTENT_INDEX_EXTS.contains(&ext.as_str()))
+}
+
+/// 把 token 切分成 (行号, 行内偏移) 两部分并小写化，供索引与查询共用
+fn tokenize(content: &str) -> FxHashMap<String, Vec<Hit>> {
+    let mut tokens: FxHashMap<String, Vec<Hit>> = FxHashMap::default();
+
+    for (line_idx, line) in content.lines().enumerate() {
+        let line_lower = line.to_lowercase();
+        for word in line_lower.split(|c: char| !c.is_alphanumeric() && c != '_') {
+            if !word.is_empty() {
+                let offset = word.as_ptr() as usize - line_lower.as_ptr() as usize;
+                tokens
+                    .entry(word.to_string())
+                    .or_default()
+                    .push((line_idx + 1, offset));
+            }
+        }
+    }
+
+    tokens
+}
+
+/// USN action 1/2/3（新增/修改）时调用：读取文件内容并（重新）分词建立索引。
+/// 扩展名不在允许列表、或读取/解码失败时静默跳过，不影响文件名索引本身
+pub fn index_file(drive: char, path: &str) {
+    if !is_content_indexable(path) {
+        return;
+    }
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+
+    let tokens = tokenize(&content);
+    INDICES
+        .write()
+        .entry(drive.to_ascii_uppercase())
+        .or_default()
+        .index_path(path, tokens);
+}
+
+/// USN action 0/4（删除）时调用：把该文件贡献的内容 token 一并清理，不留悬挂条目
+pub fn purge_file(drive: char, path: &str) {
+    if let Some(index) = INDICES.write().get_mut(&drive.to_ascii_uppercase()) {
+        index.purge_path(path);
+    }
+}
+
+/// 类 grep 的全文搜索：返回所有索引驱动器中命中 `query`（整词，忽略大小写）的行/偏移
+pub fn search_content(query: &str, max_results: usize) -> Vec<ContentHit> {
+    let token = query.to_lowercase();
+    let mut results = Vec::new();
+
+    for (&drive, index) in INDICES.read().iter() {
+        if let Some(paths) = index.inverted.get(&token) {
+            for (path, hits) in paths {
+                for &(line, offset) in hits {
+                    results.push(ContentHit {
+                        drive,
+                        path: path.clone(),
+                        line,
+                        offset,
+                    });
+                    if results.len() >= max_results {
+                        return results;
+                    }
+                }
+            }
+        }
+    }
+
+    results
+}
+
+// ============== 按需 grep 扫描（不依赖预建倒排索引） ==============
+//
+// `search_content` 只能命中已经被 USN 增量管道分词过的文本文件；下面这组函数走另一条路：
+// 现读候选文件内容、逐行扫描，支持字面量和正则两种模式，返回行号+摘要，和 ripgrep 的结果形状接近。
+// 候选文件列表由调用方（通常是按文件名/扩展名在 `SearchIndex` 里先筛一遍）传入，避免对整棵树
+// 的每个文件都开一次句柄。
+
+/// 探测前几 KB 是否出现 NUL 字节：和 grep/ripgrep 的二进制探测启发式一致，
+/// 命中就跳过，避免把图片/可执行文件当文本硬读
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// 摘要最长保留的字符数，超出部分截断并加省略号，避免把整行超长内容传回前端
+const SNIPPET_MAX_CHARS: usize = 200;
+
+/// 单次 grep 命中：文件路径 + 行号（从 1 开始）+ 该行摘要
+#[derive(Clone, Debug, Serialize)]
+pub struct ContentMatch {
+    pub path: String,
+    pub line: usize,
+    pub snippet: String,
+}
+
+fn looks_binary(path: &Path) -> bool {
+    use std::io::Read;
+    let mut file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return true,
+    };
+    let mut buf = [0u8; BINARY_SNIFF_BYTES];
+    let n = match file.read(&mut buf) {
+        Ok(n) => n,
+        Err(_) => return true,
+    };
+    buf[..n].contains(&0)
+}
+
+fn truncate_snippet(line: &str) -> String {
+    if line.chars().count() <= SNIPPET_MAX_CHARS {
+        line.to_string()
+    } else {
+        let head: String = line.chars().take(SNIPPET_MAX_CHARS).collect();
+        format!("{}…", head)
+    }
+}
+
+/// 对单个文件做字面量逐行扫描：先用 `memchr` 在整行字节上找一次子串再决定要不要收进结果，
+/// 比逐字符比较快；大小写不敏感，和仓库里其它搜索入口的习惯一致
+fn grep_literal_file(path: &str, needle_lower: &str, max_matches: usize) -> Vec<ContentMatch> {
+    let p = Path::new(path);
+    if looks_binary(p) {
+        return Vec::new();
+    }
+    let content = match std::fs::read_to_string(p) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut matches = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line_lower = line.to_lowercase();
+        if memchr::memmem::find(line_lower.as_bytes(), needle_lower.as_bytes()).is_some() {
+            matches.push(ContentMatch {
+                path: path.to_string(),
+                line: idx + 1,
+                snippet: truncate_snippet(line),
+            });
+            if matches.len() >= max_matches {
+                break;
+            }
+        }
+    }
+    matches
+}
+
+/// 对单个文件做正则逐行扫描，用于 `regex:` 查询
+fn grep_regex_file(path: &str, re: &Regex, max_matches: usize) -> Vec<ContentMatch> {
+    let p = Path::new(path);
+    if looks_binary(p) {
+        return Vec::new();
+    }
+    let content = match std::fs::read_to_string(p) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut matches = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        if re.is_match(line) {
+            matches.push(ContentMatch {
+                path: path.to_string(),
+                line: idx + 1,
+                snippet: truncate_snippet(line),
+            });
+            if matches.len() >= max_matches {
+                break;
+            }
+        }
+    }
+    matches
+}
+
+/// grep 式全文内容搜索：对 `candidates`（通常是文件名/扩展名索引已经先筛过一遍的路径列表）
+/// 并行逐个打开扫描，命中数到 `max_total` 就不再继续收集。`is_regex` 为 false 时走字面量
+/// 快速子串扫描，为 true 时走正则（大小写不敏感），正则编译失败直接返回错误
+pub fn grep_search(
+    candidates: &[String],
+    pattern: &str,
+    is_regex: bool,
+    max_matches_per_file: usize,
+    max_total: usize,
+) -> Result<Vec<ContentMatch>, String> {
+    use rayon::prelude::*;
+
+    let regex = if is_regex {
+        Some(
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("正则表达式无效: {}", e))?,
+        )
+    } else {
+        None
+    };
+    let needle_lower = pattern.to_lowercase();
+
+    let per_file: Vec<Vec<ContentMatch>> = candidates
+        .par_iter()
+        .map(|path| match &regex {
+            Some(re) => grep_regex_file(path, re, max_matches_per_file),
+            None => grep_literal_file(path, &needle_lower, max_matches_per_file),
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(max_total.min(1024));
+    for file_matches in per_file {
+        results.extend(file_matches);
+        if results.len() >= max_total {
+            results.truncate(max_total);
+            break;
+        }
+    }
+    Ok(results)
+}
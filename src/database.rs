@@ -1,11 +1,19 @@
 //! SQLite 数据库操作
 
-use rusqlite::{Connection, params};
+use rusqlite::{params, params_from_iter, types::ToSql, Connection};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// 快照文件头：8 字节魔数 + 4 字节格式版本 + 8 字节记录数 + 8 字节 build_time + 32 字节
+/// blake3 校验和，后面紧跟着记录流
+const SNAPSHOT_MAGIC: &[u8; 8] = b"FSESNAP1";
+const SNAPSHOT_VERSION: u32 = 1;
+const SNAPSHOT_HEADER_LEN: usize = 8 + 4 + 8 + 8 + 32;
+
 /// 文件条目
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
     pub name: String,
     pub name_lower: String,
@@ -14,19 +22,153 @@ pub struct FileEntry {
     pub extension: String,
     pub size: u64,
     pub mtime: f64,
-    pub is_dir: bool,
+    pub file_type: FileType,
+    /// 符号链接指向的目标路径；非符号链接或目标未解析出来时为 `None`
+    pub link_target: Option<String>,
+}
+
+/// 真实文件系统不止文件/目录——符号链接、命名管道、设备节点、socket 都存在，裸
+/// `is_dir: bool` 表达不了。存储为 `files.file_type` 这一列上的小整数，`File`/`Dir`
+/// 取值特意保持在旧 `is_dir` 的 0/1 上，`migrate_is_dir_column` 迁移时可以直接照搬取值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileType {
+    File = 0,
+    Dir = 1,
+    SymLink = 2,
+    Pipe = 3,
+    CharDevice = 4,
+    BlockDevice = 5,
+    Socket = 6,
+}
+
+impl FileType {
+    pub fn is_dir(&self) -> bool {
+        matches!(self, FileType::Dir)
+    }
+
+    fn from_i64(v: i64) -> Self {
+        match v {
+            1 => FileType::Dir,
+            2 => FileType::SymLink,
+            3 => FileType::Pipe,
+            4 => FileType::CharDevice,
+            5 => FileType::BlockDevice,
+            6 => FileType::Socket,
+            _ => FileType::File,
+        }
+    }
+}
+
+/// `files_fts` 用的分词器：`Default` 只支持 token 前缀匹配，`Trigram` 支持任意子串匹配
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizerMode {
+    Default,
+    Trigram,
+}
+
+impl TokenizerMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TokenizerMode::Default => "default",
+            TokenizerMode::Trigram => "trigram",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "default" => Some(TokenizerMode::Default),
+            "trigram" => Some(TokenizerMode::Trigram),
+            _ => None,
+        }
+    }
 }
 
+/// `Query::pattern` 的匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// `filename_lower` 全等
+    Exact,
+    /// `filename_lower` 前缀匹配，走 `idx_filename_lower` 索引
+    Prefix,
+    /// `filename_lower` 上的 SQLite `GLOB`（`*`/`?`/`[...]`）
+    Glob,
+    /// `files_fts MATCH`，按 `?1` 对应的 `files_fts` 分词规则匹配（见 `TokenizerMode`）
+    Fts,
+}
+
+/// 结果排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Mtime,
+    Size,
+    PathLen,
+}
+
+impl SortKey {
+    fn sql_expr(&self) -> &'static str {
+        match self {
+            SortKey::Name => "filename_lower",
+            SortKey::Mtime => "mtime",
+            SortKey::Size => "size",
+            SortKey::PathLen => "LENGTH(full_path)",
+        }
+    }
+}
+
+/// 对 `Database::search` 的一次查询：匹配模式 + 可选过滤条件 + 排序 + 分页
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub pattern: String,
+    pub mode: MatchMode,
+    pub ext: Option<String>,
+    pub parent_dir: Option<String>,
+    pub is_dir: Option<bool>,
+    pub sort: SortKey,
+    pub ascending: bool,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            mode: MatchMode::Prefix,
+            ext: None,
+            parent_dir: None,
+            is_dir: None,
+            sort: SortKey::Name,
+            ascending: true,
+            limit: 200,
+            offset: 0,
+        }
+    }
+}
+
+/// 一次 `sync_drive` 增量同步的变化量统计
+#[derive(Debug, Clone, Default)]
+pub struct SyncStats {
+    pub added: u64,
+    pub changed: u64,
+    pub removed: u64,
+}
+
+/// `retry_on_busy` 默认的最大重试次数
+const DEFAULT_BUSY_RETRY_LIMIT: u32 = 5;
+
 /// 数据库管理器
 pub struct Database {
     conn: Connection,
+    /// 写操作撞上 SQLITE_BUSY/SQLITE_LOCKED 时的最大重试次数，见 `retry_on_busy`
+    busy_retry_limit: u32,
 }
 
 impl Database {
     /// 创建或打开数据库
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let conn = Connection::open(path)?;
-        
+
         // 极限优化配置
         conn.execute_batch("
             PRAGMA synchronous = OFF;
@@ -49,19 +191,94 @@ impl Database {
                 extension TEXT,
                 size INTEGER DEFAULT 0,
                 mtime REAL DEFAULT 0,
-                is_dir INTEGER DEFAULT 0
+                file_type INTEGER DEFAULT 0,
+                link_target TEXT
             );
-            
+
             CREATE INDEX IF NOT EXISTS idx_filename_lower ON files(filename_lower);
             CREATE INDEX IF NOT EXISTS idx_parent_dir ON files(parent_dir);
-            
+
             CREATE TABLE IF NOT EXISTS meta (
                 key TEXT PRIMARY KEY,
                 value TEXT
             );
+
+            CREATE TABLE IF NOT EXISTS updates (
+                drive TEXT NOT NULL,
+                ts REAL NOT NULL,
+                added INTEGER NOT NULL,
+                changed INTEGER NOT NULL,
+                removed INTEGER NOT NULL
+            );
         ")?;
-        
-        Ok(Self { conn })
+
+        Self::migrate_is_dir_column(&conn)?;
+
+        Ok(Self {
+            conn,
+            busy_retry_limit: DEFAULT_BUSY_RETRY_LIMIT,
+        })
+    }
+
+    /// 覆盖默认的 SQLITE_BUSY/SQLITE_LOCKED 重试上限
+    pub fn set_busy_retry_limit(&mut self, limit: u32) {
+        self.busy_retry_limit = limit;
+    }
+
+    /// 从一个已经打开好的连接（例如 `open_reader` 返回的只读连接）套上 `Database` 外壳，
+    /// 这样 `search` 之类的查询方法可以直接套用在并发只读连接上，不需要重新 `open`
+    pub fn from_connection(conn: Connection) -> Self {
+        Self { conn, busy_retry_limit: DEFAULT_BUSY_RETRY_LIMIT }
+    }
+
+    /// 打开一个只读连接，供索引构建期间的并发查询使用。要求数据库已经（或即将）处于
+    /// WAL 模式——`restore_normal_mode` 切到 WAL 之前，`PRAGMA locking_mode = EXCLUSIVE`
+    /// 仍然会让只读连接也读不到数据，这个限制由调用方负责，这里不重复处理
+    pub fn open_reader<P: AsRef<Path>>(path: P) -> Result<Connection, Box<dyn std::error::Error + Send + Sync>> {
+        let conn = Connection::open_with_flags(
+            path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.execute_batch("PRAGMA query_only = TRUE;")?;
+        Ok(conn)
+    }
+
+    /// 早期版本的 `files` 表只有 `is_dir INTEGER`，`file_type` 列是后加的。检测到旧列
+    /// 还在、新列还没有时原地升级：新增 `file_type`/`link_target`，把 `is_dir` 的 0/1
+    /// 照搬进 `file_type`（两者在 `File`/`Dir` 上取值一致），能删列的 SQLite（>= 3.35）
+    /// 顺手删掉 `is_dir`，删不掉也不影响后续代码——不会再有人读这一列
+    fn migrate_is_dir_column(conn: &Connection) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let has_is_dir = Self::column_exists(conn, "files", "is_dir")?;
+        let has_file_type = Self::column_exists(conn, "files", "file_type")?;
+
+        if has_is_dir && !has_file_type {
+            conn.execute_batch(
+                "
+                ALTER TABLE files ADD COLUMN file_type INTEGER NOT NULL DEFAULT 0;
+                ALTER TABLE files ADD COLUMN link_target TEXT;
+                UPDATE files SET file_type = is_dir;
+            ",
+            )?;
+            let _ = conn.execute("ALTER TABLE files DROP COLUMN is_dir", []);
+        }
+
+        Ok(())
+    }
+
+    fn column_exists(
+        conn: &Connection,
+        table: &str,
+        column: &str,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(1)?;
+            if name == column {
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
     
     /// 清空所有文件记录
@@ -73,25 +290,72 @@ impl Database {
     /// 删除指定驱动器的记录
     pub fn delete_drive(&self, drive: char) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
         let pattern = format!("{}:%", drive.to_ascii_uppercase());
-        let count = self.conn.execute(
-            "DELETE FROM files WHERE full_path LIKE ?1 || '%'",
-            [&pattern],
-        )?;
+        let count = retry_on_busy(self.busy_retry_limit, || {
+            self.conn
+                .execute("DELETE FROM files WHERE full_path LIKE ?1 || '%'", [&pattern])
+        })?;
         Ok(count as u64)
     }
     
-    /// 批量插入文件记录
-    pub fn insert_batch(&mut self, entries: &[FileEntry]) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    /// 增量同步某个驱动器的扫描结果：和 `clear_all` + `insert_batch` 整表重建不同，只对
+    /// 真正变化的路径做写入——新路径 `INSERT`，`(mtime, size)` 变了的路径 `UPDATE`，
+    /// 这次扫描里没再出现的旧路径 `DELETE`。同一批扫描之间复用同一个事务，变化量记一条
+    /// `updates` 日志，供调用方展示"本次新增/修改/删除了多少个文件"
+    pub fn sync_drive(
+        &mut self,
+        drive: char,
+        entries: &[FileEntry],
+    ) -> Result<SyncStats, Box<dyn std::error::Error + Send + Sync>> {
+        let pattern = format!("{}:%", drive.to_ascii_uppercase());
         let tx = self.conn.transaction()?;
-        
+
+        // 读出该驱动器当前已有的 (full_path -> (mtime, size))，作为比对基准
+        let mut existing: std::collections::HashMap<String, (f64, u64)> =
+            std::collections::HashMap::new();
         {
-            let mut stmt = tx.prepare_cached(
-                "INSERT OR IGNORE INTO files (filename, filename_lower, full_path, parent_dir, extension, size, mtime, is_dir)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+            let mut stmt =
+                tx.prepare("SELECT full_path, mtime, size FROM files WHERE full_path LIKE ?1 || '%'")?;
+            let mut rows = stmt.query([&pattern])?;
+            while let Some(row) = rows.next()? {
+                let path: String = row.get(0)?;
+                let mtime: f64 = row.get(1)?;
+                let size: i64 = row.get(2)?;
+                existing.insert(path, (mtime, size as u64));
+            }
+        }
+
+        let mut stats = SyncStats::default();
+        let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+        {
+            let mut upsert_stmt = tx.prepare_cached(
+                "INSERT INTO files (filename, filename_lower, full_path, parent_dir, extension, size, mtime, file_type, link_target)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT(full_path) DO UPDATE SET
+                    filename = excluded.filename,
+                    filename_lower = excluded.filename_lower,
+                    parent_dir = excluded.parent_dir,
+                    extension = excluded.extension,
+                    size = excluded.size,
+                    mtime = excluded.mtime,
+                    file_type = excluded.file_type,
+                    link_target = excluded.link_target",
             )?;
-            
+
             for entry in entries {
-                stmt.execute(params![
+                seen.insert(entry.full_path.clone());
+
+                let is_new = match existing.get(&entry.full_path) {
+                    Some(&(old_mtime, old_size)) => {
+                        if old_mtime == entry.mtime && old_size == entry.size {
+                            continue; // 没变化，跳过写入
+                        }
+                        false
+                    }
+                    None => true,
+                };
+
+                upsert_stmt.execute(params![
                     &entry.name,
                     &entry.name_lower,
                     &entry.full_path,
@@ -99,62 +363,180 @@ impl Database {
                     &entry.extension,
                     entry.size as i64,
                     entry.mtime,
-                    if entry.is_dir { 1 } else { 0 },
+                    entry.file_type as i64,
+                    &entry.link_target,
                 ])?;
+
+                if is_new {
+                    stats.added += 1;
+                } else {
+                    stats.changed += 1;
+                }
             }
         }
-        
-        // 更新元数据
+
+        // 这次扫描没再出现的旧路径视为已从磁盘移除
+        {
+            let mut delete_stmt = tx.prepare_cached("DELETE FROM files WHERE full_path = ?1")?;
+            for path in existing.keys() {
+                if !seen.contains(path) {
+                    delete_stmt.execute([path])?;
+                    stats.removed += 1;
+                }
+            }
+        }
+
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs_f64();
-        
-        tx.execute(
-            "INSERT OR REPLACE INTO meta (key, value) VALUES ('build_time', ?1)",
-            [now.to_string()],
-        )?;
-        
         tx.execute(
-            "INSERT OR REPLACE INTO meta (key, value) VALUES ('used_mft', '1')",
-            [],
+            "INSERT INTO updates (drive, ts, added, changed, removed) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                drive.to_ascii_uppercase().to_string(),
+                now,
+                stats.added as i64,
+                stats.changed as i64,
+                stats.removed as i64,
+            ],
         )?;
-        
+
         tx.commit()?;
-        
-        Ok(entries.len() as u64)
+        Ok(stats)
+    }
+
+    /// 批量插入文件记录
+    pub fn insert_batch(&mut self, entries: &[FileEntry]) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let limit = self.busy_retry_limit;
+        let conn = &mut self.conn;
+
+        let count = retry_on_busy(limit, move || -> rusqlite::Result<u64> {
+            let tx = conn.transaction()?;
+
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT OR IGNORE INTO files (filename, filename_lower, full_path, parent_dir, extension, size, mtime, file_type, link_target)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+                )?;
+
+                for entry in entries {
+                    stmt.execute(params![
+                        &entry.name,
+                        &entry.name_lower,
+                        &entry.full_path,
+                        &entry.parent_dir,
+                        &entry.extension,
+                        entry.size as i64,
+                        entry.mtime,
+                        entry.file_type as i64,
+                        &entry.link_target,
+                    ])?;
+                }
+            }
+
+            // 更新元数据
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs_f64();
+
+            tx.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('build_time', ?1)",
+                [now.to_string()],
+            )?;
+
+            tx.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('used_mft', '1')",
+                [],
+            )?;
+
+            tx.commit()?;
+
+            Ok(entries.len() as u64)
+        })?;
+
+        Ok(count)
     }
     
-    /// 构建 FTS5 全文索引
+    /// 构建 FTS5 全文索引，默认用 FTS5 内置分词器（只支持前缀匹配，见 `build_fts_mode`）
     pub fn build_fts(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // 先删除旧的 FTS 表和触发器
-        self.conn.execute_batch("
-            DROP TRIGGER IF EXISTS files_ai;
-            DROP TRIGGER IF EXISTS files_ad;
-            DROP TABLE IF EXISTS files_fts;
-        ")?;
-        
-        // 创建新的 FTS 表
-        self.conn.execute_batch("
-            CREATE VIRTUAL TABLE files_fts USING fts5(
-                filename,
-                content = files,
-                content_rowid = id
-            );
-            
-            INSERT INTO files_fts(files_fts) VALUES('rebuild');
-            
-            CREATE TRIGGER files_ai AFTER INSERT ON files BEGIN
-                INSERT INTO files_fts(rowid, filename) VALUES (new.id, new.filename);
-            END;
-            
-            CREATE TRIGGER files_ad AFTER DELETE ON files BEGIN
-                INSERT INTO files_fts(files_fts, rowid, filename) VALUES('delete', old.id, old.filename);
-            END;
-        ")?;
-        
+        self.build_fts_mode(TokenizerMode::Default)
+    }
+
+    /// 按指定分词器构建 FTS5 全文索引。默认分词器只能匹配到 token 前缀，`*foo*` 这种
+    /// 片段中间的子串查询会静默失效；`Trigram`（需要 SQLite >= 3.34）对任意 3 个字符
+    /// 以上的片段做连续子串匹配，代价是索引体积更大。选用的模式记录进 `meta` 表，
+    /// 供查询层判断 `LIKE '%frag%'` 之类的操作符当前是否可用
+    pub fn build_fts_mode(
+        &self,
+        mode: TokenizerMode,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let create_table_sql = match mode {
+            TokenizerMode::Default => {
+                "CREATE VIRTUAL TABLE files_fts USING fts5(
+                    filename,
+                    content = files,
+                    content_rowid = id
+                );"
+            }
+            TokenizerMode::Trigram => {
+                "CREATE VIRTUAL TABLE files_fts USING fts5(
+                    filename,
+                    content = files,
+                    content_rowid = id,
+                    tokenize = 'trigram'
+                );"
+            }
+        };
+
+        retry_on_busy(self.busy_retry_limit, || -> rusqlite::Result<()> {
+            // 先删除旧的 FTS 表和触发器（两种分词器共用同一张 files_fts/同一对触发器）
+            self.conn.execute_batch(
+                "
+                DROP TRIGGER IF EXISTS files_ai;
+                DROP TRIGGER IF EXISTS files_ad;
+                DROP TABLE IF EXISTS files_fts;
+            ",
+            )?;
+
+            self.conn.execute_batch(create_table_sql)?;
+            self.conn.execute_batch(
+                "
+                INSERT INTO files_fts(files_fts) VALUES('rebuild');
+
+                CREATE TRIGGER files_ai AFTER INSERT ON files BEGIN
+                    INSERT INTO files_fts(rowid, filename) VALUES (new.id, new.filename);
+                END;
+
+                CREATE TRIGGER files_ad AFTER DELETE ON files BEGIN
+                    INSERT INTO files_fts(files_fts, rowid, filename) VALUES('delete', old.id, old.filename);
+                END;
+            ",
+            )?;
+
+            self.conn.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('fts_tokenizer', ?1)",
+                [mode.as_str()],
+            )?;
+
+            Ok(())
+        })?;
+
         Ok(())
     }
+
+    /// 当前 FTS 表是用哪种分词器构建的；还没建过 FTS 索引时返回 `None`
+    pub fn fts_tokenizer_mode(&self) -> Result<Option<TokenizerMode>, Box<dyn std::error::Error + Send + Sync>> {
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'fts_tokenizer'",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(value.and_then(|v| TokenizerMode::parse(&v)))
+    }
     
     /// 恢复正常的数据库模式
     pub fn restore_normal_mode(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -176,4 +558,321 @@ impl Database {
         )?;
         Ok(count as u64)
     }
-}
\ No newline at end of file
+
+    /// 统一查询入口：封装 `Query` 描述的匹配模式 + 过滤 + 排序 + 分页，调用方不必直接拿到
+    /// `conn` 拼 SQL。`Exact`/`Prefix` 走 `filename_lower` 上的 `idx_filename_lower` 索引
+    /// （大小写不敏感是免费的，因为这一列本来就存的小写），`Fts` 把 `files_fts MATCH` 的结果
+    /// 按 `content_rowid` 关联回 `files` 拿到完整行
+    pub fn search(&self, query: &Query) -> Result<Vec<FileEntry>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conds: Vec<String> = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+        let from_clause;
+
+        match query.mode {
+            MatchMode::Fts => {
+                from_clause = "files_fts JOIN files ON files.id = files_fts.rowid".to_string();
+                conds.push("files_fts MATCH ?".to_string());
+                values.push(Box::new(query.pattern.clone()));
+            }
+            MatchMode::Exact => {
+                from_clause = "files".to_string();
+                conds.push("filename_lower = ?".to_string());
+                values.push(Box::new(query.pattern.to_lowercase()));
+            }
+            MatchMode::Prefix => {
+                from_clause = "files".to_string();
+                // 范围比较能用上 BINARY 排序的 idx_filename_lower，`LIKE` 在这一列上用不到索引
+                let lower = query.pattern.to_lowercase();
+                conds.push("filename_lower >= ? AND filename_lower < ?".to_string());
+                values.push(Box::new(lower.clone()));
+                values.push(Box::new(format!("{}\u{10FFFF}", lower)));
+            }
+            MatchMode::Glob => {
+                from_clause = "files".to_string();
+                conds.push("filename_lower GLOB ?".to_string());
+                values.push(Box::new(query.pattern.to_lowercase()));
+            }
+        }
+
+        if let Some(ext) = &query.ext {
+            conds.push("extension = ?".to_string());
+            values.push(Box::new(ext.to_lowercase()));
+        }
+        if let Some(parent_dir) = &query.parent_dir {
+            conds.push("parent_dir = ?".to_string());
+            values.push(Box::new(parent_dir.clone()));
+        }
+        // `Query::is_dir` 是早期的粗粒度过滤，现在折算成 `file_type`：true 只要 `Dir`，
+        // false 要除 `Dir` 以外的一切（符号链接/设备节点等也算"不是目录"）
+        if let Some(is_dir) = query.is_dir {
+            conds.push(format!("file_type {} ?", if is_dir { "=" } else { "!=" }));
+            values.push(Box::new(FileType::Dir as i64));
+        }
+
+        let order = if query.ascending { "ASC" } else { "DESC" };
+        let sql = format!(
+            "SELECT files.filename, files.filename_lower, files.full_path, files.parent_dir, \
+             files.extension, files.size, files.mtime, files.file_type, files.link_target \
+             FROM {} WHERE {} ORDER BY {} {} LIMIT ? OFFSET ?",
+            from_clause,
+            conds.join(" AND "),
+            query.sort.sql_expr(),
+            order,
+        );
+        values.push(Box::new(query.limit));
+        values.push(Box::new(query.offset));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values.iter().map(|v| v.as_ref())), |row| {
+            Ok(FileEntry {
+                name: row.get(0)?,
+                name_lower: row.get(1)?,
+                full_path: row.get(2)?,
+                parent_dir: row.get(3)?,
+                extension: row.get(4)?,
+                size: row.get::<_, i64>(5)? as u64,
+                mtime: row.get(6)?,
+                file_type: FileType::from_i64(row.get(7)?),
+                link_target: row.get(8)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// 把 `files` 表整个导出成一份 mmap 友好的快照：定长头（魔数/格式版本/记录数/
+    /// `build_time`/校验和）后面跟着逐条 `u32 len + bincode(FileEntry)` 的记录流。
+    /// 冷启动时 `load_snapshot` 直接 mmap 这份文件顺序回放，省掉 SQLite 逐行解码的开销
+    pub fn export_snapshot<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT filename, filename_lower, full_path, parent_dir, extension, size, mtime, \
+             file_type, link_target FROM files ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(FileEntry {
+                name: row.get(0)?,
+                name_lower: row.get(1)?,
+                full_path: row.get(2)?,
+                parent_dir: row.get(3)?,
+                extension: row.get(4)?,
+                size: row.get::<_, i64>(5)? as u64,
+                mtime: row.get(6)?,
+                file_type: FileType::from_i64(row.get(7)?),
+                link_target: row.get(8)?,
+            })
+        })?;
+
+        let mut payload = Vec::new();
+        let mut count: u64 = 0;
+        for row in rows {
+            let entry = row?;
+            let bytes = bincode::serialize(&entry)?;
+            payload.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            payload.extend_from_slice(&bytes);
+            count += 1;
+        }
+
+        let build_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        let checksum = blake3::hash(&payload);
+
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(SNAPSHOT_MAGIC)?;
+        file.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        file.write_all(&count.to_le_bytes())?;
+        file.write_all(&build_time.to_le_bytes())?;
+        file.write_all(checksum.as_bytes())?;
+        file.write_all(&payload)?;
+
+        Ok(count)
+    }
+
+    /// `export_snapshot` 的反向操作：mmap 整个文件，校验头部魔数/版本/校验和，再顺序切出
+    /// 每条记录喂给 `insert_batch`（按批提交，避免把整份快照先物化成一个巨大的 `Vec`）。
+    /// 校验和不匹配（快照被截断、磁盘损坏等）时返回错误而不是喂脏数据——调用方应当退回
+    /// 全量重建（重新扫描 + `insert_batch`），而不是信任这份快照
+    pub fn load_snapshot<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let file = std::fs::File::open(path)?;
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        let data: &[u8] = &mmap;
+
+        if data.len() < SNAPSHOT_HEADER_LEN {
+            return Err("snapshot file is truncated (shorter than header)".into());
+        }
+        if &data[0..8] != SNAPSHOT_MAGIC {
+            return Err("snapshot file has an unrecognized magic number".into());
+        }
+        let version = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        if version != SNAPSHOT_VERSION {
+            return Err(format!("unsupported snapshot format version {}", version).into());
+        }
+        let record_count = u64::from_le_bytes(data[12..20].try_into().unwrap());
+        let _build_time = f64::from_le_bytes(data[20..28].try_into().unwrap());
+        let stored_checksum = &data[28..SNAPSHOT_HEADER_LEN];
+        let payload = &data[SNAPSHOT_HEADER_LEN..];
+
+        if blake3::hash(payload).as_bytes() != stored_checksum {
+            return Err("snapshot checksum mismatch, refusing to load".into());
+        }
+
+        const FLUSH_BATCH_SIZE: usize = 5000;
+        let mut batch = Vec::with_capacity(FLUSH_BATCH_SIZE);
+        let mut cursor = 0usize;
+        let mut inserted: u64 = 0;
+
+        while cursor + 4 <= payload.len() {
+            let len = u32::from_le_bytes(payload[cursor..cursor + 4].try_into().unwrap()) as usize;
+            cursor += 4;
+            if cursor + len > payload.len() {
+                return Err("snapshot record stream ended mid-record".into());
+            }
+            let entry: FileEntry = bincode::deserialize(&payload[cursor..cursor + len])?;
+            cursor += len;
+
+            batch.push(entry);
+            if batch.len() >= FLUSH_BATCH_SIZE {
+                inserted += self.insert_batch(&batch)?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            inserted += self.insert_batch(&batch)?;
+        }
+
+        if inserted != record_count {
+            log::warn!(
+                "snapshot header declared {} records but {} were loaded",
+                record_count,
+                inserted
+            );
+        }
+
+        Ok(inserted)
+    }
+}
+
+/// 带指数退避的重试封装：索引正在写入时，另一个写操作可能撞上 SQLITE_BUSY（被其他
+/// 连接占用）或 SQLITE_LOCKED（同一连接内的表级锁冲突），这两种都值得退避后重试；
+/// 其他错误（约束冲突、SQL 语法错误等）重试没有意义，直接透传给调用方
+fn retry_on_busy<T>(limit: u32, mut f: impl FnMut() -> rusqlite::Result<T>) -> rusqlite::Result<T> {
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Ok(v) => return Ok(v),
+            Err(e) if attempt < limit && is_busy_or_locked(&e) => {
+                let backoff_ms = 20u64 * (1u64 << attempt.min(10));
+                std::thread::sleep(std::time::Duration::from_millis(backoff_ms));
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn is_busy_or_locked(err: &rusqlite::Error) -> bool {
+    matches!(
+        err,
+        rusqlite::Error::SqliteFailure(ffi_err, _)
+            if matches!(
+                ffi_err.code,
+                rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+            )
+    )
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_entry(name: &str) -> FileEntry {
+        FileEntry {
+            name: name.to_string(),
+            name_lower: name.to_lowercase(),
+            full_path: format!("C:\\{}", name),
+            parent_dir: "C:\\".to_string(),
+            extension: "txt".to_string(),
+            size: 123,
+            mtime: 0.0,
+            file_type: FileType::File,
+            link_target: None,
+        }
+    }
+
+    fn temp_db_path(label: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "database_test_{}_{}.db",
+            label,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_export_and_load_snapshot_round_trip() {
+        let db_path = temp_db_path("snapshot_src");
+        let snapshot_path = temp_db_path("snapshot_file");
+        std::fs::remove_file(&db_path).ok();
+        std::fs::remove_file(&snapshot_path).ok();
+
+        let mut source = Database::new(&db_path).expect("创建源数据库应当成功");
+        source
+            .insert_batch(&[sample_entry("a.txt"), sample_entry("b.txt")])
+            .expect("insert_batch 应当成功");
+        let exported = source
+            .export_snapshot(&snapshot_path)
+            .expect("export_snapshot 应当成功");
+        assert_eq!(exported, 2);
+
+        let target_path = temp_db_path("snapshot_dst");
+        std::fs::remove_file(&target_path).ok();
+        let mut target = Database::new(&target_path).expect("创建目标数据库应当成功");
+        let loaded = target
+            .load_snapshot(&snapshot_path)
+            .expect("load_snapshot 应当能读回刚导出的快照");
+        assert_eq!(loaded, 2);
+        assert_eq!(target.get_file_count().unwrap(), 2);
+
+        std::fs::remove_file(&db_path).ok();
+        std::fs::remove_file(&target_path).ok();
+        std::fs::remove_file(&snapshot_path).ok();
+    }
+
+    /// 回归测试：快照被截断/篡改时 `load_snapshot` 必须报错退回全量重建，而不是把
+    /// 部分记录悄悄喂进数据库
+    #[test]
+    fn test_load_snapshot_rejects_checksum_mismatch() {
+        let db_path = temp_db_path("snapshot_corrupt_src");
+        let snapshot_path = temp_db_path("snapshot_corrupt_file");
+        std::fs::remove_file(&db_path).ok();
+        std::fs::remove_file(&snapshot_path).ok();
+
+        let source = Database::new(&db_path).expect("创建源数据库应当成功");
+        source
+            .export_snapshot(&snapshot_path)
+            .expect("export_snapshot 应当成功（哪怕是空表）");
+
+        let mut bytes = std::fs::read(&snapshot_path).expect("应当能读回刚导出的快照");
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        std::fs::write(&snapshot_path, &bytes).expect("应当能写回篡改后的快照");
+
+        let target_path = temp_db_path("snapshot_corrupt_dst");
+        std::fs::remove_file(&target_path).ok();
+        let mut target = Database::new(&target_path).expect("创建目标数据库应当成功");
+        assert!(target.load_snapshot(&snapshot_path).is_err());
+
+        std::fs::remove_file(&db_path).ok();
+        std::fs::remove_file(&target_path).ok();
+        std::fs::remove_file(&snapshot_path).ok();
+    }
+}
@@ -0,0 +1,172 @@
+// 设备热插拔监听 - 通过隐藏窗口接收 WM_DEVICECHANGE 消息
+// 让新插入的 U 盘自动建立索引，拔出的盘及时释放内存中的索引
+
+#[cfg(target_os = "windows")]
+mod win {
+    use crate::SEARCH_INDICES;
+    use parking_lot::RwLock;
+    use std::ffi::c_void;
+    use std::sync::LazyLock;
+    use tauri::Manager;
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, PostQuitMessage,
+        RegisterClassW, TranslateMessage, CW_USEDEFAULT, HWND_MESSAGE, MSG, WM_DEVICECHANGE,
+        WM_DESTROY, WNDCLASSW,
+    };
+
+    /// 监听线程需要跨 `extern "system"` 回调访问 AppHandle 来发送事件，放在静态里最简单
+    static APP_HANDLE: LazyLock<RwLock<Option<tauri::AppHandle>>> =
+        LazyLock::new(|| RwLock::new(None));
+
+    const DBT_DEVICEARRIVAL: u32 = 0x8000;
+    const DBT_DEVICEREMOVECOMPLETE: u32 = 0x8004;
+    const DBT_DEVTYP_VOLUME: u32 = 0x0002;
+
+    #[repr(C)]
+    struct DevBroadcastHdr {
+        dbch_size: u32,
+        dbch_devicetype: u32,
+        dbch_reserved: u32,
+    }
+
+    #[repr(C)]
+    struct DevBroadcastVolume {
+        dbcv_size: u32,
+        dbcv_devicetype: u32,
+        dbcv_reserved: u32,
+        dbcv_unitmask: u32,
+        dbcv_flags: u16,
+    }
+
+    /// 把 `dbcv_unitmask` 的 bit0..25 转换回盘符（bit0 = A, bit1 = B, ...）
+    fn unit_mask_to_drive(mask: u32) -> Option<char> {
+        for bit in 0..26u32 {
+            if mask & (1 << bit) != 0 {
+                return Some((b'A' + bit as u8) as char);
+            }
+        }
+        None
+    }
+
+    unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match msg {
+            WM_DEVICECHANGE => {
+                let event = wparam as u32;
+                if (event == DBT_DEVICEARRIVAL || event == DBT_DEVICEREMOVECOMPLETE) && lparam != 0 {
+                    let hdr = &*(lparam as *const DevBroadcastHdr);
+                    if hdr.dbch_devicetype == DBT_DEVTYP_VOLUME {
+                        let vol = &*(lparam as *const DevBroadcastVolume);
+                        if let Some(drive) = unit_mask_to_drive(vol.dbcv_unitmask) {
+                            if event == DBT_DEVICEARRIVAL {
+                                on_drive_arrived(drive);
+                            } else {
+                                on_drive_removed(drive);
+                            }
+                        }
+                    }
+                }
+                0
+            }
+            WM_DESTROY => {
+                PostQuitMessage(0);
+                0
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+
+    fn emit_drive_event(event: &str, drive: char) {
+        if let Some(handle) = APP_HANDLE.read().as_ref() {
+            let _ = handle.emit_all(event, drive.to_string());
+        }
+    }
+
+    fn on_drive_arrived(drive: char) {
+        log::info!("🔌 检测到驱动器插入: {}:", drive);
+        emit_drive_event("drive-arrived", drive);
+        std::thread::spawn(move || {
+            if crate::init_search_index_internal(drive) {
+                log::info!("✅ 新插入驱动器 {} 索引构建完成", drive);
+            } else {
+                log::warn!("⚠️ 新插入驱动器 {} 索引构建失败", drive);
+            }
+        });
+    }
+
+    fn on_drive_removed(drive: char) {
+        log::info!("🔌 检测到驱动器移除: {}:", drive);
+        SEARCH_INDICES.write().remove(&drive.to_ascii_uppercase());
+        emit_drive_event("drive-removed", drive);
+    }
+
+    /// 在独立线程上创建一个隐藏消息窗口并运行消息循环，持续监听 WM_DEVICECHANGE
+    pub fn spawn(app_handle: tauri::AppHandle) {
+        *APP_HANDLE.write() = Some(app_handle);
+
+        std::thread::spawn(|| unsafe {
+            let class_name: Vec<u16> = "FileScannerDeviceWatch\0".encode_utf16().collect();
+            let hinstance = GetModuleHandleW(std::ptr::null());
+
+            let wc = WNDCLASSW {
+                style: 0,
+                lpfnWndProc: Some(wnd_proc),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: hinstance,
+                hIcon: 0,
+                hCursor: 0,
+                hbrBackground: 0,
+                lpszMenuName: std::ptr::null(),
+                lpszClassName: class_name.as_ptr(),
+            };
+
+            if RegisterClassW(&wc) == 0 {
+                log::warn!("⚠️ 注册设备监听窗口类失败，热插盘检测不可用");
+                return;
+            }
+
+            let hwnd = CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                std::ptr::null(),
+                0,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                HWND_MESSAGE,
+                0,
+                hinstance,
+                std::ptr::null() as *const c_void,
+            );
+
+            if hwnd == 0 {
+                log::warn!("⚠️ 创建设备监听窗口失败，热插盘检测不可用");
+                return;
+            }
+
+            log::info!("👁️ 设备热插拔监听已启动");
+
+            let mut msg: MSG = std::mem::zeroed();
+            while GetMessageW(&mut msg, 0, 0, 0) > 0 {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        });
+    }
+}
+
+/// 启动设备热插拔监听（仅 Windows 有效，其它平台为空操作）
+pub fn spawn_device_watch(#[cfg_attr(not(target_os = "windows"), allow(unused_variables))] app_handle: tauri::AppHandle) {
+    #[cfg(target_os = "windows")]
+    {
+        win::spawn(app_handle);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        log::info!("ℹ️ 当前平台不支持设备热插拔监听");
+    }
+}
@@ -0,0 +1,321 @@
+// duplicates.rs - 基于内存索引的重复文件检测
+// 大小分桶 -> 首尾局部哈希分桶 -> 全文件内容哈希确认，复用在建索引，避免重新遍历磁盘
+
+use crate::search_index::{IndexedItem, SearchIndex};
+use parking_lot::{Mutex, RwLock};
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+
+/// 局部哈希默认读取的首/尾字节数
+const DEFAULT_PARTIAL_HASH_BYTES: usize = 16 * 1024;
+
+/// 一组确认重复的文件
+#[derive(Clone, Debug, Serialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub size: u64,
+    pub paths: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DuplicateOptions {
+    pub ignore_zero_length: bool,
+    pub partial_hash_bytes: usize,
+    /// 只在这些扩展名（不含点，大小写不敏感）中查找重复，为空表示不限制
+    pub ext_filter: Option<Vec<String>>,
+    /// 只在路径以此前缀开头的文件中查找重复（大小写不敏感），为空表示不限制
+    pub path_prefix: Option<String>,
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        Self {
+            ignore_zero_length: true,
+            partial_hash_bytes: DEFAULT_PARTIAL_HASH_BYTES,
+            ext_filter: None,
+            path_prefix: None,
+        }
+    }
+}
+
+impl DuplicateOptions {
+    fn filter_key(&self) -> (Option<Vec<String>>, Option<String>) {
+        (
+            self.ext_filter
+                .as_ref()
+                .map(|exts| exts.iter().map(|e| e.to_lowercase()).collect()),
+            self.path_prefix.as_ref().map(|p| p.to_lowercase()),
+        )
+    }
+
+    fn matches(&self, item: &IndexedItem) -> bool {
+        if let Some(exts) = &self.ext_filter {
+            if !exts.iter().any(|e| item.extension.eq_ignore_ascii_case(e)) {
+                return false;
+            }
+        }
+        if let Some(prefix) = &self.path_prefix {
+            if !item.path.to_lowercase().starts_with(&prefix.to_lowercase()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 流式扫描进度：按阶段报告已处理/总数，供前端展示进度条并决定是否取消
+#[derive(Clone, Debug, Serialize)]
+pub struct DuplicateScanProgress {
+    pub stage: &'static str,
+    pub processed: usize,
+    pub total: usize,
+}
+
+/// 每个索引一份扫描结果缓存，按 (索引版本号, 过滤条件) 作为 key：索引 `version()` 没变
+/// 且过滤条件相同就直接复用上次的分组结果，USN 监控触发的 add_file/remove_file 会让
+/// version 变化，从而使缓存自然失效；被取消的扫描不写入缓存
+struct DuplicateFinder {
+    cache: Mutex<Option<(u64, (Option<Vec<String>>, Option<String>), Vec<DuplicateGroup>)>>,
+}
+
+impl DuplicateFinder {
+    fn new() -> Self {
+        Self { cache: Mutex::new(None) }
+    }
+
+    fn find(
+        &self,
+        index: &SearchIndex,
+        options: &DuplicateOptions,
+        cancel: &AtomicBool,
+        on_progress: &mut dyn FnMut(DuplicateScanProgress),
+    ) -> Vec<DuplicateGroup> {
+        let version = index.version();
+        let filter_key = options.filter_key();
+
+        {
+            let cache = self.cache.lock();
+            if let Some((cached_version, cached_key, groups)) = cache.as_ref() {
+                if *cached_version == version && *cached_key == filter_key {
+                    return groups.clone();
+                }
+            }
+        }
+
+        let groups = match scan(index, options, cancel, on_progress) {
+            Some(groups) => groups,
+            None => return Vec::new(), // 扫描被取消，不缓存不完整的结果
+        };
+        *self.cache.lock() = Some((version, filter_key, groups.clone()));
+        groups
+    }
+}
+
+static FINDERS: LazyLock<RwLock<FxHashMap<char, DuplicateFinder>>> =
+    LazyLock::new(|| RwLock::new(FxHashMap::default()));
+
+/// 每个驱动器一个取消标志：`cancel_scan` 置位后，正在运行的 `scan` 在下一个
+/// 检查点提前返回 `None`
+static CANCEL_FLAGS: LazyLock<RwLock<FxHashMap<char, std::sync::Arc<AtomicBool>>>> =
+    LazyLock::new(|| RwLock::new(FxHashMap::default()));
+
+/// 正在扫描中的驱动器集合：`find_duplicates_for_drive` 曾经对每次调用都无条件覆盖
+/// `CANCEL_FLAGS[drive]`，如果同一驱动器上有两次扫描并发（双击 UI、或 `scope:"all"`
+/// 期间又手动点了一次），较早那次扫描持有的取消标志就被换掉，`cancel_scan` 从此再也
+/// 取消不到它——这里用这个集合把"同一驱动器只能有一个扫描在跑"显式拒绝掉，而不是
+/// 让后来者静默地使前者变得不可取消
+static RUNNING: LazyLock<Mutex<rustc_hash::FxHashSet<char>>> =
+    LazyLock::new(|| Mutex::new(rustc_hash::FxHashSet::default()));
+
+/// `RUNNING` 插入标记的 RAII 守卫：`find` 本身可能 panic（比如某个文件的 IO 出问题），
+/// 单纯的 insert/remove 配对在这种情况下会漏掉 remove，导致该驱动器从此永久被挡在
+/// `RUNNING` 外面，要重启进程才能恢复——用 `Drop` 保证无论正常返回还是 panic 都会释放
+struct RunningGuard(char);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING.lock().remove(&self.0);
+    }
+}
+
+/// 取消某个驱动器正在进行的重复文件扫描；若当前没有扫描在跑，这是个空操作
+pub fn cancel_scan(drive: char) {
+    let drive = drive.to_ascii_uppercase();
+    if let Some(flag) = CANCEL_FLAGS.read().get(&drive) {
+        flag.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 查找某个驱动器索引中的重复文件，结果按 (索引版本号, 过滤条件) 缓存；
+/// `on_progress` 在按大小分桶（`bucket_by_size`）和逐桶哈希（`hash_partial`，局部与全量哈希
+/// 都在这一阶段内完成）两个阶段汇报进度，结束时再报一次 `done`。同一驱动器同一时间
+/// 只允许一个扫描在跑，重叠的请求会被拒绝而不是悄悄抢走前一个扫描的取消标志
+pub fn find_duplicates_for_drive(
+    drive: char,
+    index: &SearchIndex,
+    options: &DuplicateOptions,
+    on_progress: &mut dyn FnMut(DuplicateScanProgress),
+) -> Result<Vec<DuplicateGroup>, String> {
+    let drive = drive.to_ascii_uppercase();
+
+    if !RUNNING.lock().insert(drive) {
+        return Err(format!("{} 盘已有一个重复文件扫描正在进行中", drive));
+    }
+    let _running_guard = RunningGuard(drive);
+
+    let cancel = {
+        let flag = std::sync::Arc::new(AtomicBool::new(false));
+        CANCEL_FLAGS.write().insert(drive, flag.clone());
+        flag
+    };
+
+    let groups = {
+        let finders = FINDERS.read();
+        if let Some(finder) = finders.get(&drive) {
+            Some(finder.find(index, options, &cancel, on_progress))
+        } else {
+            None
+        }
+    };
+    let groups = match groups {
+        Some(groups) => groups,
+        None => {
+            let finder = DuplicateFinder::new();
+            let groups = finder.find(index, options, &cancel, on_progress);
+            FINDERS.write().insert(drive, finder);
+            groups
+        }
+    };
+
+    Ok(groups)
+}
+
+fn scan(
+    index: &SearchIndex,
+    options: &DuplicateOptions,
+    cancel: &AtomicBool,
+    on_progress: &mut dyn FnMut(DuplicateScanProgress),
+) -> Option<Vec<DuplicateGroup>> {
+    let items = index.all_items();
+
+    // 第一轮：按文件大小分桶，大小唯一的文件不可能有重复；同时应用 ext:/路径前缀过滤
+    let mut by_size: FxHashMap<u64, Vec<&IndexedItem>> = FxHashMap::default();
+    for (i, item) in items.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        if item.is_dir {
+            continue;
+        }
+        if options.ignore_zero_length && item.size == 0 {
+            continue;
+        }
+        if !options.matches(item) {
+            continue;
+        }
+        by_size.entry(item.size).or_default().push(item);
+        if i % 4096 == 0 {
+            on_progress(DuplicateScanProgress {
+                stage: "bucket_by_size",
+                processed: i,
+                total: items.len(),
+            });
+        }
+    }
+
+    let mut groups = Vec::new();
+    let size_buckets: Vec<(u64, Vec<&IndexedItem>)> = by_size
+        .into_iter()
+        .filter(|(_, candidates)| candidates.len() >= 2)
+        .collect();
+    let total_buckets = size_buckets.len();
+
+    for (bucket_idx, (size, candidates)) in size_buckets.into_iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        on_progress(DuplicateScanProgress {
+            stage: "hash_partial",
+            processed: bucket_idx,
+            total: total_buckets,
+        });
+
+        // 第二轮：按文件首尾的局部哈希再分桶，廉价地排除大部分假阳性
+        let mut by_partial: FxHashMap<u64, Vec<&IndexedItem>> = FxHashMap::default();
+        for item in candidates {
+            if let Some(h) = hash_partial(&item.path, size, options.partial_hash_bytes) {
+                by_partial.entry(h).or_default().push(item);
+            }
+        }
+
+        for (_partial_hash, same_partial) in by_partial {
+            if same_partial.len() < 2 {
+                continue;
+            }
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            // 第三轮：只对仍然碰撞的文件计算全文件内容哈希，确认真正重复
+            let mut by_full: FxHashMap<String, Vec<String>> = FxHashMap::default();
+            for item in same_partial {
+                if let Some(h) = hash_full_file(&item.path) {
+                    by_full.entry(h).or_default().push(item.path.clone());
+                }
+            }
+
+            for (hash, paths) in by_full {
+                if paths.len() < 2 {
+                    continue;
+                }
+                groups.push(DuplicateGroup { hash, size, paths });
+            }
+        }
+    }
+
+    on_progress(DuplicateScanProgress {
+        stage: "done",
+        processed: total_buckets,
+        total: total_buckets,
+    });
+
+    Some(groups)
+}
+
+/// 读取文件首部和尾部各 `max_bytes` 字节一起哈希，比只读首部更不容易把“开头相同、
+/// 中间不同”的文件误判为碰撞；文件本身小于 `2 * max_bytes` 时首尾区间重叠，
+/// 退化为对整个文件哈希
+fn hash_partial(path: &str, size: u64, max_bytes: usize) -> Option<u64> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = blake3::Hasher::new();
+
+    let mut head = vec![0u8; max_bytes];
+    let n = file.read(&mut head).ok()?;
+    hasher.update(&head[..n]);
+
+    if size > max_bytes as u64 * 2 {
+        file.seek(SeekFrom::End(-(max_bytes as i64))).ok()?;
+        let mut tail = vec![0u8; max_bytes];
+        let n = file.read(&mut tail).ok()?;
+        hasher.update(&tail[..n]);
+    } else {
+        let mut rest = Vec::new();
+        file.read_to_end(&mut rest).ok()?;
+        hasher.update(&rest);
+    }
+
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest.as_bytes()[..8]);
+    Some(u64::from_le_bytes(bytes))
+}
+
+fn hash_full_file(path: &str) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finalize().to_hex().to_string())
+}
@@ -6,9 +6,16 @@ pub mod search_index;
 pub mod commands;
 pub mod config;
 pub mod shortcuts;
-pub mod index_engine;
 pub mod search_syntax;
-
+pub mod devicewatch;
+pub mod duplicates;
+pub mod changes;
+pub mod content_index;
+pub mod batch_actions;
+pub mod persistence;
+pub mod database;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use parking_lot::RwLock;
 use rayon::prelude::*;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -19,7 +26,10 @@ use std::fs;
 use std::os::raw::c_char;
 use std::os::windows::ffi::OsStrExt;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, LazyLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use search_index::{IndexedItem, SearchIndex};
 
@@ -42,6 +52,19 @@ struct PersistDirCacheV1 {
     paths: Vec<(u64, String)>,
 }
 
+/// V1 的 zstd 压缩版：路径表先 `bincode` 序列化再整体压缩，体积通常能降到 V1 的几分之一。
+/// `load_dir_cache` 仍然兼容读取旧的 V1 文件，但新写入一律是 V2
+#[derive(Serialize, Deserialize)]
+struct PersistDirCacheV2 {
+    version: u32,
+    drive: u8,
+    journal_id: u64,
+    /// 压缩前 `bincode::serialize(&Vec<(u64, String)>)` 的字节数，解压后做一次完整性校验
+    uncompressed_len: u64,
+    /// `zstd` 压缩后的路径表
+    compressed: Vec<u8>,
+}
+
 // ============== FFI 结构 ==============
 
 #[repr(C)]
@@ -63,6 +86,8 @@ pub struct FileChange {
 pub struct ChangeList {
     pub changes: *mut FileChange,
     pub count: usize,
+    /// USN journal 已被系统回收/重建，旧游标不再有效，调用方应触发全量重建而不是继续增量拉取
+    pub journal_invalid: u8,
 }
 
 #[repr(C)]
@@ -77,6 +102,14 @@ pub struct UsnChangeResult {
 pub struct FileInfo {
     pub size: u64,
     pub mtime: f64,
+    /// 创建时间，Unix 秒（浮点数）
+    pub ctime: f64,
+    /// 最后访问时间，Unix 秒（浮点数）
+    pub atime: f64,
+    /// 创建时间原始 FILETIME tick（100ns），需要亚秒精度的调用方可以用这个而不是 `ctime`
+    pub ctime_raw: i64,
+    /// 最后访问时间原始 FILETIME tick（100ns）
+    pub atime_raw: i64,
     pub exists: u8,
 }
 
@@ -97,6 +130,38 @@ pub struct SearchItemFFI {
     pub size: u64,
     pub is_dir: u8,
     pub mtime: f64,
+    /// 创建时间，Unix 秒（浮点数）
+    pub ctime: f64,
+    /// 最后访问时间，Unix 秒（浮点数）
+    pub atime: f64,
+    /// 创建时间原始 FILETIME tick（100ns）
+    pub ctime_raw: i64,
+    /// 最后访问时间原始 FILETIME tick（100ns）
+    pub atime_raw: i64,
+    /// 内容 grep 命中的首个字节偏移；不是内容搜索结果时固定为 -1
+    pub first_match_offset: i64,
+}
+
+/// `search_query` 的组合过滤条件。C ABI 没有 `Option`，所以每个可选数值字段配一个
+/// `has_*` 标志位，标志位为 0 时对应字段不参与过滤；`ext_filter_ptr`/`name_contains_ptr`
+/// 用空指针表示"不限制"。各字段语义与 `search_index::FilterQuery` 一一对应
+#[repr(C)]
+pub struct SearchQueryFFI {
+    pub name_contains_ptr: *const c_char,
+    /// 逗号分隔的扩展名列表（不含点），如 "pdf,docx"；空指针或空串表示不限制
+    pub ext_filter_ptr: *const c_char,
+    pub has_size_min: u8,
+    pub size_min: u64,
+    pub has_size_max: u8,
+    pub size_max: u64,
+    pub has_mtime_min: u8,
+    pub mtime_min: f64,
+    pub has_mtime_max: u8,
+    pub mtime_max: f64,
+    /// -1 = 不限制，0 = 只要文件，1 = 只要目录
+    pub is_dir: i8,
+    pub attrs_include: u32,
+    pub attrs_exclude: u32,
 }
 
 // ============== 全局搜索索引缓存 ==============
@@ -104,6 +169,42 @@ pub struct SearchItemFFI {
 pub static SEARCH_INDICES: LazyLock<RwLock<FxHashMap<char, Arc<SearchIndex>>>> =
     LazyLock::new(|| RwLock::new(FxHashMap::default()));
 
+/// 已挂载的 ISO 虚拟卷索引：不像真实驱动器那样有单字母可用，镜像文件路径本身就是键
+static ISO_INDICES: LazyLock<RwLock<FxHashMap<String, Arc<SearchIndex>>>> =
+    LazyLock::new(|| RwLock::new(FxHashMap::default()));
+
+/// 每个驱动器的后台去抖动持久化 worker，USN 监控循环增量更新索引时用它入队变更，
+/// 落盘节奏由 worker 内部的 debounce 计时器控制，不阻塞监控循环本身
+static PERSISTENCE_HANDLES: LazyLock<RwLock<FxHashMap<char, persistence::PersistenceHandle>>> =
+    LazyLock::new(|| RwLock::new(FxHashMap::default()));
+
+/// USN 监控循环落盘的去抖动周期
+const PERSISTENCE_DEBOUNCE_SECS: u64 = 10;
+
+/// 取出某个驱动器的持久化 worker 句柄，不存在就基于当前内存索引懒启动一个
+pub(crate) fn get_or_spawn_persistence_handle(drive: char) -> Option<()> {
+    {
+        let handles = PERSISTENCE_HANDLES.read();
+        if handles.contains_key(&drive) {
+            return Some(());
+        }
+    }
+
+    let index = SEARCH_INDICES.read().get(&drive).cloned()?;
+    let index_path = std::path::PathBuf::from(format!("{}:\\.search_index.bin", drive));
+    let handle = index.spawn_persistence(index_path, std::time::Duration::from_secs(PERSISTENCE_DEBOUNCE_SECS));
+    PERSISTENCE_HANDLES.write().insert(drive, handle);
+    Some(())
+}
+
+/// 给某个驱动器的持久化 worker 入队一条增量变更；worker 不存在时懒启动
+pub(crate) fn enqueue_persistence_update(drive: char, update: persistence::IndexUpdate) {
+    get_or_spawn_persistence_handle(drive);
+    if let Some(handle) = PERSISTENCE_HANDLES.read().get(&drive) {
+        handle.enqueue(update);
+    }
+}
+
 // ============== 过滤规则 ==============
 
 const SKIP_DIRS: &[&str] = &[
@@ -191,6 +292,74 @@ fn should_skip_ext_fast(filename: &str, skip_exts: &FxHashSet<&str>) -> bool {
     false
 }
 
+/// 用户可自定义的忽略规则：在内置 SKIP_DIRS/SKIP_EXTS 之外，叠加
+/// 全局配置目录下的 `ignore_rules.txt`、`Config::excluded_dirs`、以及盘符根目录下的
+/// `.gitignore`/`.ignore`，用 `ignore` crate 编译成一份 gitignore 语法的匹配器，
+/// 支持否定模式（`!keep_this/`）和通配符——这些都是扁平 HashSet 精确匹配表达不了的。
+/// 每次 `build_index`/`force_rebuild` 开始扫描时构建一份，整次扫描复用同一份编译结果。
+/// 内置的 SKIP_DIRS/SKIP_EXTS 快速路径保持不变，这份规则是叠加在其上的额外一层。
+struct IgnoreRules {
+    matcher: Option<Gitignore>,
+}
+
+impl IgnoreRules {
+    fn build(root: &str) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        let mut has_any = false;
+
+        if let Ok(manager) = config::ConfigManager::new() {
+            for dir in &manager.get().excluded_dirs {
+                if builder.add_line(None, dir).is_ok() {
+                    has_any = true;
+                }
+            }
+        }
+
+        if let Some(global_path) = global_ignore_file_path() {
+            if global_path.exists() {
+                match builder.add(&global_path) {
+                    Some(e) => log::warn!("加载全局忽略规则文件失败: {}", e),
+                    None => has_any = true,
+                }
+            }
+        }
+
+        for name in [".gitignore", ".ignore"] {
+            let p = Path::new(root).join(name);
+            if p.exists() {
+                match builder.add(&p) {
+                    Some(e) => log::warn!("加载 {} 失败: {}", p.display(), e),
+                    None => has_any = true,
+                }
+            }
+        }
+
+        if !has_any {
+            return Self { matcher: None };
+        }
+
+        match builder.build() {
+            Ok(m) => Self { matcher: Some(m) },
+            Err(e) => {
+                log::warn!("编译自定义忽略规则失败，本次扫描跳过自定义规则: {}", e);
+                Self { matcher: None }
+            }
+        }
+    }
+
+    #[inline]
+    fn is_extra_ignored(&self, path: &str, is_dir: bool) -> bool {
+        match &self.matcher {
+            Some(m) => m.matched(path, is_dir).is_ignore(),
+            None => false,
+        }
+    }
+}
+
+fn global_ignore_file_path() -> Option<std::path::PathBuf> {
+    dirs::config_dir().map(|d| d.join("filesearch").join("ignore_rules.txt"))
+}
+
 #[inline]
 fn get_ext_lower(filename: &str) -> String {
     if let Some(pos) = filename.rfind('.') {
@@ -238,9 +407,50 @@ extern "system" {
 
 const EPOCH_DIFF: u64 = 116444736000000000;
 
+/// `get_file_info_fast` 一次 `GetFileAttributesExW` 里能免费拿到的全部元数据。
+/// `mtime`/`ctime`/`atime` 是转成 Unix 秒的浮点值（和 `IndexedItem::mtime` 的约定一致），
+/// `ctime_raw`/`atime_raw` 额外保留原始 FILETIME 100ns tick，避免 f64 秒数丢掉亚秒精度
+struct FileStatFast {
+    size: u64,
+    mtime: f64,
+    ctime: f64,
+    atime: f64,
+    ctime_raw: i64,
+    atime_raw: i64,
+    is_reparse_point: bool,
+    /// Windows 文件属性位掩码（只读/隐藏/系统/存档等），和 `is_reparse_point` 一样是同一次
+    /// `GetFileAttributesExW` 里免费带出来的，不需要额外系统调用
+    attrs: u32,
+}
+
+/// 给路径加上 `\\?\` 扩展前缀，绕开 `MAX_PATH`（260 字符）限制；UNC 路径（`\\server\share\...`）
+/// 要用专门的 `\\?\UNC\server\share\...` 形式，否则 Win32 API 仍然按 MAX_PATH 截断。
+/// 已经带前缀、或是 `\\.\` 设备路径的输入原样返回，避免重复加前缀
+fn to_extended_length_path(path: &str) -> String {
+    if path.starts_with("\\\\?\\") || path.starts_with("\\\\.\\") {
+        return path.to_string();
+    }
+    if let Some(rest) = path.strip_prefix("\\\\") {
+        format!("\\\\?\\UNC\\{}", rest)
+    } else {
+        format!("\\\\?\\{}", path)
+    }
+}
+
+/// 把 FILETIME（100ns tick，1601-01-01 纪元）转成 Unix 秒；早于 Unix 纪元时返回 0.0
+#[inline]
+fn filetime_to_unix_secs(ft: u64) -> f64 {
+    if ft > EPOCH_DIFF {
+        (ft - EPOCH_DIFF) as f64 / 10_000_000.0
+    } else {
+        0.0
+    }
+}
+
+/// reparse 标记复用已经取到的 file_attributes，不产生额外系统调用
 #[inline]
-fn get_file_info_fast(path: &str) -> Option<(u64, f64)> {
-    let wide: Vec<u16> = OsStr::new(path)
+fn get_file_info_fast(path: &str) -> Option<FileStatFast> {
+    let wide: Vec<u16> = OsStr::new(&to_extended_length_path(path))
         .encode_wide()
         .chain(std::iter::once(0))
         .collect();
@@ -253,18 +463,286 @@ fn get_file_info_fast(path: &str) -> Option<(u64, f64)> {
             let size = ((data.file_size_high as u64) << 32) | (data.file_size_low as u64);
             let mtime_ft =
                 ((data.last_write_time_high as u64) << 32) | (data.last_write_time_low as u64);
-            let mtime = if mtime_ft > EPOCH_DIFF {
-                (mtime_ft - EPOCH_DIFF) as f64 / 10_000_000.0
-            } else {
-                0.0
-            };
-            Some((size, mtime))
+            let ctime_ft =
+                ((data.creation_time_high as u64) << 32) | (data.creation_time_low as u64);
+            let atime_ft =
+                ((data.last_access_time_high as u64) << 32) | (data.last_access_time_low as u64);
+            let is_reparse_point = (data.file_attributes & FILE_ATTRIBUTE_REPARSE_POINT) != 0;
+            Some(FileStatFast {
+                size,
+                mtime: filetime_to_unix_secs(mtime_ft),
+                ctime: filetime_to_unix_secs(ctime_ft),
+                atime: filetime_to_unix_secs(atime_ft),
+                ctime_raw: ctime_ft as i64,
+                atime_raw: atime_ft as i64,
+                is_reparse_point,
+                attrs: data.file_attributes,
+            })
         } else {
             None
         }
     }
 }
 
+const FSCTL_GET_REPARSE_POINT: u32 = 0x000900a8;
+const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x00200000;
+const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xa0000003;
+const IO_REPARSE_TAG_SYMLINK: u32 = 0xa000000c;
+const MAXIMUM_REPARSE_DATA_BUFFER_SIZE: usize = 16 * 1024;
+/// 跟随重解析点目标的最大跳数：防止链接指向自身/另一条链接时无限递归
+const MAX_SYMLINK_FOLLOW: usize = 8;
+
+/// 解析 `FSCTL_GET_REPARSE_POINT` 返回的 REPARSE_DATA_BUFFER，取出替代名（substitute name）。
+/// MOUNT_POINT（junction）和 SYMLINK 的头 8 字节之后都是
+/// SubstituteNameOffset/Length, PrintNameOffset/Length（各 u16），
+/// 区别只在于 SYMLINK 多一个 4 字节 Flags，再往后才是公共的 PathBuffer
+fn parse_reparse_buffer(buf: &[u8], bytes_returned: u32) -> Option<String> {
+    if (bytes_returned as usize) < 8 {
+        return None;
+    }
+
+    let tag = u32::from_le_bytes(buf[0..4].try_into().ok()?);
+
+    let header_len = match tag {
+        IO_REPARSE_TAG_MOUNT_POINT => 8,
+        IO_REPARSE_TAG_SYMLINK => 8 + 4,
+        _ => return None,
+    };
+
+    let sub_offset = u16::from_le_bytes(buf[header_len..header_len + 2].try_into().ok()?) as usize;
+    let sub_len = u16::from_le_bytes(buf[header_len + 2..header_len + 4].try_into().ok()?) as usize;
+    let path_buffer_start = header_len + 8;
+    let start = path_buffer_start + sub_offset;
+    let end = start + sub_len;
+    if end > bytes_returned as usize || end > buf.len() {
+        return None;
+    }
+
+    let name_u16: Vec<u16> = buf[start..end]
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let name = String::from_utf16_lossy(&name_u16);
+
+    // NT 命名空间前缀（\??\C:\...）对上层没有意义，去掉以得到普通 Win32 路径
+    Some(name.strip_prefix("\\??\\").unwrap_or(&name).to_string())
+}
+
+/// 读取单个重解析点（junction/symlink）的替代名（substitute name），不跟随目标再判断
+/// 目标本身是否还是重解析点——那部分由 [`follow_reparse_chain`] 的跳数循环负责
+fn read_reparse_point_raw(path: &str) -> Option<String> {
+    use windows_sys::Win32::Foundation::*;
+    use windows_sys::Win32::Storage::FileSystem::*;
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    let wide: Vec<u16> = OsStr::new(&to_extended_length_path(path))
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    unsafe {
+        let handle = CreateFileW(
+            wide.as_ptr(),
+            0, // 只读取重解析点元数据，不需要读写文件内容的权限
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            std::ptr::null(),
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT,
+            0,
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return None;
+        }
+
+        let mut buf = vec![0u8; MAXIMUM_REPARSE_DATA_BUFFER_SIZE];
+        let mut bytes_returned: u32 = 0;
+        let ok = DeviceIoControl(
+            handle,
+            FSCTL_GET_REPARSE_POINT,
+            std::ptr::null(),
+            0,
+            buf.as_mut_ptr() as _,
+            buf.len() as u32,
+            &mut bytes_returned,
+            std::ptr::null_mut(),
+        );
+        CloseHandle(handle);
+
+        if ok == 0 {
+            return None;
+        }
+
+        parse_reparse_buffer(&buf, bytes_returned)
+    }
+}
+
+/// 跟 [`read_reparse_point_raw`] 一样读替代名，但按文件引用号（`file_ref`）在已打开的卷句柄上
+/// 通过 `OpenFileById` 打开，供 MFT 全量扫描场景使用——这时还没有现成的路径字符串可以
+/// 传给 `CreateFileW`，只有卷句柄和 USN 记录里的文件引用号
+fn read_reparse_point_by_ref(
+    volume_handle: windows_sys::Win32::Foundation::HANDLE,
+    file_ref: u64,
+) -> Option<String> {
+    use windows_sys::Win32::Foundation::*;
+    use windows_sys::Win32::Storage::FileSystem::*;
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    unsafe {
+        #[repr(C)]
+        struct FILE_ID_DESCRIPTOR {
+            dw_size: u32,
+            id_type: u32,
+            file_id: u64,
+        }
+
+        let desc = FILE_ID_DESCRIPTOR {
+            dw_size: std::mem::size_of::<FILE_ID_DESCRIPTOR>() as u32,
+            id_type: 0,
+            file_id: file_ref,
+        };
+
+        let handle = OpenFileById(
+            volume_handle,
+            &desc as *const _ as *const _,
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            std::ptr::null(),
+            FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT,
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return None;
+        }
+
+        let mut buf = vec![0u8; MAXIMUM_REPARSE_DATA_BUFFER_SIZE];
+        let mut bytes_returned: u32 = 0;
+        let ok = DeviceIoControl(
+            handle,
+            FSCTL_GET_REPARSE_POINT,
+            std::ptr::null(),
+            0,
+            buf.as_mut_ptr() as _,
+            buf.len() as u32,
+            &mut bytes_returned,
+            std::ptr::null_mut(),
+        );
+        CloseHandle(handle);
+
+        if ok == 0 {
+            return None;
+        }
+
+        parse_reparse_buffer(&buf, bytes_returned)
+    }
+}
+
+/// 跟随重解析点目标，最多跳 [`MAX_SYMLINK_FOLLOW`] 次；命中上限时直接返回当时已解析到的
+/// （可能仍是重解析点的）路径，而不是继续递归——宁可留一个未完全展开的链接，也不要卡死
+fn follow_reparse_chain(path: &str) -> Option<String> {
+    let mut current = path.to_string();
+    let mut resolved = None;
+
+    for _ in 0..MAX_SYMLINK_FOLLOW {
+        match read_reparse_point_raw(&current) {
+            Some(target) => {
+                resolved = Some(target.clone());
+                current = target;
+            }
+            None => break,
+        }
+    }
+
+    resolved
+}
+
+#[repr(C)]
+pub struct ResolvedPathFFI {
+    pub ptr: *mut u8,
+    pub len: usize,
+    pub ok: u8,
+}
+
+/// 类似 `realpath`/`readlink`：不带 `FILE_FLAG_OPEN_REPARSE_POINT` 打开路径（让系统跟随重解析点），
+/// 再用 `GetFinalPathNameByHandleW` 让系统一次性解析出最终路径，比手动解析 `FSCTL_GET_REPARSE_POINT`
+/// 的替代名更可靠（能处理链式 junction/symlink，系统自己负责跳数和循环检测）
+#[no_mangle]
+pub extern "C" fn resolve_reparse_target(path_ptr: *const u8, path_len: usize) -> ResolvedPathFFI {
+    use windows_sys::Win32::Foundation::*;
+    use windows_sys::Win32::Storage::FileSystem::*;
+
+    let failed = ResolvedPathFFI {
+        ptr: std::ptr::null_mut(),
+        len: 0,
+        ok: 0,
+    };
+
+    if path_ptr.is_null() || path_len == 0 {
+        return failed;
+    }
+
+    let path = unsafe {
+        let slice = std::slice::from_raw_parts(path_ptr, path_len);
+        match std::str::from_utf8(slice) {
+            Ok(s) => s,
+            Err(_) => return failed,
+        }
+    };
+
+    let wide: Vec<u16> = OsStr::new(&to_extended_length_path(path))
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    unsafe {
+        let handle = CreateFileW(
+            wide.as_ptr(),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            std::ptr::null(),
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            0,
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return failed;
+        }
+
+        let mut path_buf = [0u16; 520];
+        let len = GetFinalPathNameByHandleW(handle as isize, path_buf.as_mut_ptr(), 520, 0);
+        CloseHandle(handle);
+
+        if len == 0 || len >= 520 {
+            return failed;
+        }
+
+        let resolved = String::from_utf16_lossy(&path_buf[..len as usize]);
+        let resolved = if let Some(unc) = resolved.strip_prefix("\\\\?\\UNC\\") {
+            format!("\\\\{}", unc)
+        } else {
+            resolved.trim_start_matches("\\\\?\\").to_string()
+        };
+
+        let bytes = resolved.into_bytes().into_boxed_slice();
+        let out_len = bytes.len();
+        let out_ptr = Box::into_raw(bytes) as *mut u8;
+
+        ResolvedPathFFI {
+            ptr: out_ptr,
+            len: out_len,
+            ok: 1,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn free_resolved_path(result: ResolvedPathFFI) {
+    if !result.ptr.is_null() && result.len > 0 {
+        unsafe {
+            let slice = std::slice::from_raw_parts_mut(result.ptr, result.len);
+            let _ = Box::<[u8]>::from_raw(slice);
+        }
+    }
+}
+
 fn get_path_by_file_ref_with_handle(
     volume_handle: windows_sys::Win32::Foundation::HANDLE,
     file_ref: u64,
@@ -306,7 +784,13 @@ fn get_path_by_file_ref_with_handle(
 
         if len > 0 && len < 520 {
             let path = String::from_utf16_lossy(&path_buf[..len as usize]);
-            Some(path.trim_start_matches("\\\\?\\").to_string())
+            // `\\?\UNC\server\share\...` 要还原成 `\\server\share\...`，不能简单砍掉 `\\?\` 前缀，
+            // 否则 UNC 路径会变成看上去像本地相对路径的 `UNC\server\share\...`
+            if let Some(unc) = path.strip_prefix("\\\\?\\UNC\\") {
+                Some(format!("\\\\{}", unc))
+            } else {
+                Some(path.trim_start_matches("\\\\?\\").to_string())
+            }
         } else {
             None
         }
@@ -320,6 +804,19 @@ struct MftRecord {
     parent_ref: u64,
     is_dir: bool,
     file_ref: u64,
+    /// junction/symlink 等重解析点；目录属性的重解析点在 MFT 里和真目录一样带 FILE_ATTRIBUTE_DIRECTORY，
+    /// 必须靠这个标记单独区分，否则路径重建会把它当成可以递归下钻的普通目录
+    is_reparse: bool,
+    /// `is_reparse` 为真时，替代名（substitute name）指向的目标路径；只有 `scan_usn_journal_all`
+    /// （全量扫描）会填充，`scan_usn_journal_quick`（目录缓存热身）不需要这个信息
+    reparse_target: Option<String>,
+    /// 字节大小；目录恒为 0。只有扫描手段本身免费带着这个数（`DirWalkScanner` 的 `FindFirstFileW`、
+    /// ISO 目录记录的 `data_len`）才会填真值，`scan_usn_journal_all`/`scan_usn_journal_quick`
+    /// 没有免费来源，留 0，真实大小由 `scan_and_pack`/`build_items_from_records` 按路径另行查询
+    size: u64,
+    /// 最近修改时间，Unix 秒。`USN_RECORD_V2.TimeStamp`、`FindFirstFileW` 的 `ftLastWriteTime`
+    /// 都是扫描本身自带的免费数据，直接转换填入；没有免费来源时留 0.0
+    timestamp: f64,
 }
 
 #[repr(C, packed)]
@@ -370,6 +867,8 @@ struct USN_RECORD_V2 {
 const FSCTL_QUERY_USN_JOURNAL: u32 = 0x000900f4;
 const FSCTL_ENUM_USN_DATA: u32 = 0x000900b3;
 const FSCTL_READ_USN_JOURNAL: u32 = 0x000900bb;
+// journal 被重建/回收后再用旧 start_usn 读取会返回这个 GetLastError 码
+const ERROR_JOURNAL_ENTRY_DELETED: u32 = 1181;
 const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x02000000;
 const FILE_FLAG_SEQUENTIAL_SCAN: u32 = 0x08000000;
 
@@ -382,12 +881,17 @@ const USN_REASON_RENAME_NEW_NAME: u32 = 0x00002000;
 const USN_REASON_CLOSE: u32 = 0x80000000;
 
 const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
 
 // Buffer 大小（优化点2：增大 USN buffer）
 const MFT_ENUM_BUFFER_SIZE: usize = 16 * 1024 * 1024;
 const USN_READ_BUFFER_SIZE: usize = 256 * 1024;
 const USN_QUICK_BUFFER_SIZE: usize = 4 * 1024 * 1024;
 
+/// `scan_and_pack` 打包格式版本号，写在返回 blob 的第一个字节。v2 把原先恒为 0 的
+/// 大小/时间字段换成了真实值，消费方靠这个字节区分新旧格式，不用再假设它们是占位 0
+const SCAN_PACK_FORMAT_VERSION: u8 = 2;
+
 // ============== FFI 导出：扫描 ==============
 
 #[no_mangle]
@@ -424,41 +928,48 @@ pub extern "C" fn free_scan_result(result: ScanResult) {
 
 // ============== FFI 导出：懒加载文件信息 ==============
 
+#[inline]
+fn empty_file_info(exists: u8) -> FileInfo {
+    FileInfo {
+        size: 0,
+        mtime: 0.0,
+        ctime: 0.0,
+        atime: 0.0,
+        ctime_raw: 0,
+        atime_raw: 0,
+        exists,
+    }
+}
+
+fn file_info_from_stat(stat: FileStatFast) -> FileInfo {
+    FileInfo {
+        size: stat.size,
+        mtime: stat.mtime,
+        ctime: stat.ctime,
+        atime: stat.atime,
+        ctime_raw: stat.ctime_raw,
+        atime_raw: stat.atime_raw,
+        exists: 1,
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn get_file_info(path_ptr: *const u8, path_len: usize) -> FileInfo {
     if path_ptr.is_null() || path_len == 0 {
-        return FileInfo {
-            size: 0,
-            mtime: 0.0,
-            exists: 0,
-        };
+        return empty_file_info(0);
     }
 
     let path = unsafe {
         let slice = std::slice::from_raw_parts(path_ptr, path_len);
         match std::str::from_utf8(slice) {
             Ok(s) => s,
-            Err(_) => {
-                return FileInfo {
-                    size: 0,
-                    mtime: 0.0,
-                    exists: 0,
-                }
-            }
+            Err(_) => return empty_file_info(0),
         }
     };
 
     match get_file_info_fast(path) {
-        Some((size, mtime)) => FileInfo {
-            size,
-            mtime,
-            exists: 1,
-        },
-        None => FileInfo {
-            size: 0,
-            mtime: 0.0,
-            exists: 0,
-        },
+        Some(stat) => file_info_from_stat(stat),
+        None => empty_file_info(0),
     }
 }
 
@@ -485,16 +996,8 @@ pub extern "C" fn get_file_info_batch(
     let results: Vec<FileInfo> = paths[..count]
         .par_iter()
         .map(|path| match get_file_info_fast(path) {
-            Some((size, mtime)) => FileInfo {
-                size,
-                mtime,
-                exists: 1,
-            },
-            None => FileInfo {
-                size: 0,
-                mtime: 0.0,
-                exists: 0,
-            },
+            Some(stat) => file_info_from_stat(stat),
+            None => empty_file_info(0),
         })
         .collect();
 
@@ -525,14 +1028,16 @@ pub extern "C" fn get_usn_changes(drive_letter: u16, last_usn: i64) -> ChangeLis
                 return ChangeList {
                     changes: std::ptr::null_mut(),
                     count: 0,
+                    journal_invalid: 0,
                 };
             }
             let ptr = Box::into_raw(changes.into_boxed_slice()) as *mut FileChange;
-            ChangeList { changes: ptr, count }
+            ChangeList { changes: ptr, count, journal_invalid: 0 }
         }
-        Err(_) => ChangeList {
+        Err(e) => ChangeList {
             changes: std::ptr::null_mut(),
             count: 0,
+            journal_invalid: (e.to_string() == "USN_JOURNAL_ENTRY_DELETED") as u8,
         },
     }
 }
@@ -652,6 +1157,7 @@ pub extern "C" fn get_changes(monitor: *mut UsnMonitor) -> ChangeList {
         return ChangeList {
             changes: std::ptr::null_mut(),
             count: 0,
+            journal_invalid: 0,
         };
     }
 
@@ -659,10 +1165,11 @@ pub extern "C" fn get_changes(monitor: *mut UsnMonitor) -> ChangeList {
         let mon = &mut *monitor;
         let result = match get_changes_since(mon.drive, mon.last_usn) {
             Ok(changes) => changes,
-            Err(_) => {
+            Err(e) => {
                 return ChangeList {
                     changes: std::ptr::null_mut(),
                     count: 0,
+                    journal_invalid: (e.to_string() == "USN_JOURNAL_ENTRY_DELETED") as u8,
                 }
             }
         };
@@ -676,11 +1183,12 @@ pub extern "C" fn get_changes(monitor: *mut UsnMonitor) -> ChangeList {
             return ChangeList {
                 changes: std::ptr::null_mut(),
                 count: 0,
+                journal_invalid: 0,
             };
         }
 
         let ptr = Box::into_raw(result.into_boxed_slice()) as *mut FileChange;
-        ChangeList { changes: ptr, count }
+        ChangeList { changes: ptr, count, journal_invalid: 0 }
     }
 }
 
@@ -759,12 +1267,27 @@ pub extern "C" fn save_dir_cache(
             paths_vec.push((*k, v.as_ref().clone()));
         }
 
-        PersistDirCacheV1 {
-            version: 1,
-            drive: drive as u8,
-            journal_id: dc.journal_id,
-            paths: paths_vec,
-        }
+        (drive as u8, dc.journal_id, paths_vec)
+    };
+
+    let (drive_byte, journal_id, paths_vec) = persisted;
+
+    let plain = match bincode::serialize(&paths_vec) {
+        Ok(b) => b,
+        Err(_) => return 0,
+    };
+    let uncompressed_len = plain.len() as u64;
+    let compressed = match zstd::stream::encode_all(&plain[..], 0) {
+        Ok(c) => c,
+        Err(_) => return 0,
+    };
+
+    let persisted = PersistDirCacheV2 {
+        version: 2,
+        drive: drive_byte,
+        journal_id,
+        uncompressed_len,
+        compressed,
     };
 
     let bytes = match bincode::serialize(&persisted) {
@@ -812,24 +1335,42 @@ pub extern "C" fn load_dir_cache(
         Err(_) => return 0,
     };
 
-    let persisted: PersistDirCacheV1 = match bincode::deserialize(&bytes) {
-        Ok(v) => v,
-        Err(_) => return 0,
-    };
-
-    if persisted.version != 1 {
-        return 0;
-    }
-    if persisted.drive != drive as u8 {
-        return 0;
-    }
-    if persisted.journal_id != journal_id_now {
+    let (drive_byte, journal_id, paths): (u8, u64, Vec<(u64, String)>) =
+        if let Ok(v2) = bincode::deserialize::<PersistDirCacheV2>(&bytes) {
+            if v2.version != 2 {
+                return 0;
+            }
+            let plain = match zstd::stream::decode_all(&v2.compressed[..]) {
+                Ok(p) => p,
+                Err(_) => return 0,
+            };
+            if plain.len() as u64 != v2.uncompressed_len {
+                return 0;
+            }
+            let paths: Vec<(u64, String)> = match bincode::deserialize(&plain) {
+                Ok(p) => p,
+                Err(_) => return 0,
+            };
+            (v2.drive, v2.journal_id, paths)
+        } else if let Ok(v1) = bincode::deserialize::<PersistDirCacheV1>(&bytes) {
+            if v1.version != 1 {
+                return 0;
+            }
+            (v1.drive, v1.journal_id, v1.paths)
+        } else {
+            return 0;
+        };
+
+    if drive_byte != drive as u8 {
+        return 0;
+    }
+    if journal_id != journal_id_now {
         return 0;
     }
 
     let mut map: FxHashMap<u64, Arc<String>> = FxHashMap::default();
-    map.reserve(persisted.paths.len());
-    for (k, v) in persisted.paths {
+    map.reserve(paths.len());
+    for (k, v) in paths {
         map.insert(k, Arc::new(v));
     }
 
@@ -1007,6 +1548,10 @@ fn get_or_build_cache(
 }
 
 // 优化点3：每个父目录只 clone 一次，避免借用冲突
+/// 借鉴 VFS 层 `VFS_MAX_FOLLOW_SYMLINK_TIMES` 的思路，给 BFS 一个硬性深度上限：
+/// 即便哪天 `is_reparse` 的判断失手漏过一个环形 junction，深度封顶也能保证这里总会终止
+const MAX_PATH_MAP_DEPTH: u32 = 16;
+
 fn build_path_map(records: &[MftRecord], drive: char) -> FxHashMap<u64, Arc<String>> {
     let root = format!("{}:\\", drive);
 
@@ -1022,12 +1567,21 @@ fn build_path_map(records: &[MftRecord], drive: char) -> FxHashMap<u64, Arc<Stri
     paths.reserve(records.len());
     paths.insert(5, Arc::new(root));
 
+    // 记录每个文件引用号是否已经入过队，配合深度上限双重防环：
+    // 正常情况下 is_reparse 跳过就足够了，这两个只是在 MFT 数据本身异常时兜底
+    let mut visited: FxHashSet<u64> = FxHashSet::default();
+    visited.insert(5);
+
     let mut queue = VecDeque::with_capacity(2000);
-    queue.push_back(5u64);
+    queue.push_back((5u64, 0u32));
 
     let mut path_buf = String::with_capacity(512);
 
-    while let Some(pid) = queue.pop_front() {
+    while let Some((pid, depth)) = queue.pop_front() {
+        if depth >= MAX_PATH_MAP_DEPTH {
+            continue;
+        }
+
         // 每个父目录 clone 一次（现在是 Arc 克隆），结束借用后才能 insert
         let parent_path_owned = match paths.get(&pid) {
             Some(p) => Arc::clone(p),
@@ -1045,7 +1599,12 @@ fn build_path_map(records: &[MftRecord], drive: char) -> FxHashMap<u64, Arc<Stri
                 path_buf.push_str(&r.filename);
 
                 paths.insert(r.file_ref, Arc::new(path_buf.clone()));
-                queue.push_back(r.file_ref);
+                // junction/symlink 指向别处，它在 MFT 里挂的"子项"其实是目标那边的记录，
+                // 顺着它递归下钻只会把目标子树在路径表里重复一遍，严重时还会把环形 junction 转成死循环；
+                // 真实目标路径已经记在 MftRecord::reparse_target 里，作为元数据单独保留
+                if !r.is_reparse && visited.insert(r.file_ref) {
+                    queue.push_back((r.file_ref, depth + 1));
+                }
             }
         }
     }
@@ -1143,8 +1702,17 @@ fn get_changes_since(
                 &mut br,
                 std::ptr::null_mut(),
             ) == 0
-                || br <= 8
             {
+                // journal 被系统回收/重建时，之前记下的 start_usn 已经失效，
+                // GetLastError 会返回 ERROR_JOURNAL_ENTRY_DELETED——这种情况下
+                // 继续增量拉取毫无意义，必须让调用方触发一次全量重扫
+                if GetLastError() == ERROR_JOURNAL_ENTRY_DELETED {
+                    CloseHandle(h);
+                    return Err("USN_JOURNAL_ENTRY_DELETED".into());
+                }
+                break;
+            }
+            if br <= 8 {
                 break;
             }
 
@@ -1264,6 +1832,292 @@ fn get_changes_since(
     }
 }
 
+/// 往 DIR_CACHE 里增量插入新目录，不用等下一次整表重建就能解析新建目录下文件的父路径。
+/// DIR_CACHE 目前只有整表替换的写法（`get_or_build_cache`/`init_search_index_internal`），
+/// 这里按同样的套路克隆旧表、插入新条目后整体换掉，避免持锁时间过长
+fn insert_dir_cache_entries(drive: char, journal_id: u64, new_dirs: Vec<(u64, Arc<String>)>) {
+    let mut cache = DIR_CACHE.write();
+    let (mut map, last_usn) = match cache.get(&drive) {
+        Some(existing) => ((*existing.paths).clone(), existing.last_usn),
+        None => (FxHashMap::default(), 0),
+    };
+    for (file_ref, path) in new_dirs {
+        map.insert(file_ref, path);
+    }
+    cache.insert(drive, DirCache { paths: Arc::new(map), journal_id, last_usn });
+}
+
+/// `subscribe_changes` 的停止句柄。Drop 时会置位停止标志并 join worker 线程，
+/// 保证卷句柄在进程退出/调用方不再关心变更前能被干净地关闭
+pub struct ChangeSubscription {
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ChangeSubscription {
+    /// 停止订阅：通知 worker 线程退出并等它关闭卷句柄
+    pub fn unsubscribe(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for ChangeSubscription {
+    fn drop(&mut self) {
+        self.unsubscribe();
+    }
+}
+
+/// 持续阻塞等待 USN journal 增长并推送变更，取代 `get_changes_since` 那种一次性轮询。
+/// 注意这是变更的*源头*：在独立线程里反复发起阻塞式 `FSCTL_READ_USN_JOURNAL`（`bytes_to_wait_for`
+/// 非 0 时该 ioctl 会一直阻塞到 journal 增长或 timeout 到期），解码出 `IndexChange` 后直接回调
+/// 给调用方；这和 `crate::changes::subscribe_changes`（进程内变更广播的*接收*端，订阅的是已经算好
+/// 的 `IndexChange`）是两回事，调用方可以在 callback 里把这里产出的变更转发给 `crate::changes::publish`
+pub fn subscribe_changes(
+    drive: char,
+    callback: impl Fn(crate::changes::IndexChange) + Send + 'static,
+) -> ChangeSubscription {
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker_stop = Arc::clone(&stop);
+    let worker = thread::spawn(move || usn_subscription_loop(drive, callback, worker_stop));
+    ChangeSubscription { stop, worker: Some(worker) }
+}
+
+/// `subscribe_changes` 的 worker 主循环，装了 `start_usn` 断点续传和 `DIR_CACHE` 增量更新的
+/// `get_changes_since` 阻塞版：journal 没有新记录时 ioctl 会阻塞在内核里，直到增长或 2 秒 timeout
+/// 到期才返回，借着这个 timeout 顺便检查一次 stop 标志，避免 unsubscribe 后线程永久卡住
+fn usn_subscription_loop(
+    drive: char,
+    callback: impl Fn(crate::changes::IndexChange),
+    stop: Arc<AtomicBool>,
+) {
+    use windows_sys::Win32::Foundation::*;
+    use windows_sys::Win32::Storage::FileSystem::*;
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    let volume: Vec<u16> = format!("\\\\.\\{}:", drive)
+        .encode_utf16()
+        .chain(Some(0))
+        .collect();
+    let root = format!("{}:\\", drive);
+
+    let h = unsafe {
+        CreateFileW(
+            volume.as_ptr(),
+            GENERIC_READ,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            std::ptr::null(),
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            0,
+        )
+    };
+    if h == INVALID_HANDLE_VALUE {
+        log::error!("📡 {} 盘变更订阅启动失败：打开卷句柄失败", drive);
+        return;
+    }
+
+    let mut jd: USN_JOURNAL_DATA_V0 = unsafe { std::mem::zeroed() };
+    let mut br: u32 = 0;
+    let queried = unsafe {
+        DeviceIoControl(
+            h,
+            FSCTL_QUERY_USN_JOURNAL,
+            std::ptr::null(),
+            0,
+            &mut jd as *mut _ as _,
+            std::mem::size_of::<USN_JOURNAL_DATA_V0>() as u32,
+            &mut br,
+            std::ptr::null_mut(),
+        )
+    };
+    if queried == 0 {
+        log::error!("📡 {} 盘变更订阅启动失败：查询 USN journal 失败", drive);
+        unsafe { CloseHandle(h) };
+        return;
+    }
+
+    let volume_serial = get_volume_serial(drive);
+    let mut start_usn = load_index_meta(drive)
+        .filter(|m| m.volume_serial == volume_serial)
+        .map(|m| m.last_usn)
+        .unwrap_or(jd.next_usn);
+
+    let reason_mask = USN_REASON_FILE_CREATE
+        | USN_REASON_FILE_DELETE
+        | USN_REASON_DATA_EXTEND
+        | USN_REASON_DATA_OVERWRITE
+        | USN_REASON_RENAME_OLD_NAME
+        | USN_REASON_RENAME_NEW_NAME
+        | USN_REASON_CLOSE;
+
+    let mut buf = vec![0u8; USN_READ_BUFFER_SIZE];
+
+    while !stop.load(Ordering::SeqCst) {
+        let paths = match get_or_build_cache(drive, h, &jd) {
+            Ok(p) => p,
+            Err(_) => {
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+        };
+
+        let read_data = READ_USN_JOURNAL_DATA_V0 {
+            start_usn,
+            reason_mask,
+            return_only_on_close: 0,
+            // 秒级超时：journal 迟迟不长也能醒过来检查一次 stop 标志
+            timeout: 2,
+            // 非 0 即可：journal 增长哪怕 1 字节也会唤醒阻塞中的 ioctl
+            bytes_to_wait_for: 1,
+            usn_journal_id: jd.usn_journal_id,
+        };
+
+        let mut br2: u32 = 0;
+        let ok = unsafe {
+            DeviceIoControl(
+                h,
+                FSCTL_READ_USN_JOURNAL,
+                &read_data as *const _ as _,
+                std::mem::size_of::<READ_USN_JOURNAL_DATA_V0>() as u32,
+                buf.as_mut_ptr() as _,
+                buf.len() as u32,
+                &mut br2,
+                std::ptr::null_mut(),
+            )
+        };
+
+        if ok == 0 {
+            if unsafe { GetLastError() } == ERROR_JOURNAL_ENTRY_DELETED {
+                log::warn!(
+                    "📡 {} 盘 USN journal 已被系统回收，变更订阅退出，调用方需要触发一次全量重扫后重新订阅",
+                    drive
+                );
+                break;
+            }
+            thread::sleep(Duration::from_millis(500));
+            continue;
+        }
+        if br2 <= 8 {
+            // 纯超时唤醒，journal 没有新记录，回到循环顶部重新检查 stop 标志
+            continue;
+        }
+
+        let next_usn = unsafe { *(buf.as_ptr() as *const i64) };
+        let mut off = 8usize;
+        let mut new_dirs: Vec<(u64, Arc<String>)> = Vec::new();
+
+        while off < br2 as usize {
+            let rec = unsafe { &*(buf.as_ptr().add(off) as *const USN_RECORD_V2) };
+            if rec.record_length == 0 {
+                break;
+            }
+
+            let noff = off + rec.file_name_offset as usize;
+            let nlen = rec.file_name_length as usize;
+
+            if noff + nlen <= br2 as usize && nlen > 0 {
+                let slice = unsafe {
+                    std::slice::from_raw_parts(buf.as_ptr().add(noff) as *const u16, nlen / 2)
+                };
+
+                if let Ok(name) = String::from_utf16(slice) {
+                    let fc = name.as_bytes().first().copied().unwrap_or(b'$');
+                    if fc != b'.' && fc != b'$' {
+                        let reason = rec.reason;
+
+                        let is_delete = (reason & USN_REASON_FILE_DELETE) != 0;
+                        let is_rename_old = (reason & USN_REASON_RENAME_OLD_NAME) != 0;
+                        let is_rename_new = (reason & USN_REASON_RENAME_NEW_NAME) != 0;
+                        let is_create = (reason & USN_REASON_FILE_CREATE) != 0;
+
+                        if !is_delete
+                            && !is_rename_old
+                            && !is_rename_new
+                            && (reason & USN_REASON_CLOSE) == 0
+                        {
+                            off += rec.record_length as usize;
+                            continue;
+                        }
+
+                        let parent_ref = rec.parent_file_reference_number & 0xFFFFFFFFFFFF;
+                        let file_ref = rec.file_reference_number & 0xFFFFFFFFFFFF;
+
+                        let mut path_buf = String::with_capacity(256);
+                        if let Some(parent_path) = paths.get(&parent_ref) {
+                            path_buf.push_str(parent_path.trim_end_matches('\\'));
+                        } else if is_rename_new || is_create {
+                            if let Some(p) = get_path_by_file_ref_with_handle(h, file_ref) {
+                                if let Some(pos) = p.rfind('\\') {
+                                    path_buf.push_str(&p[..pos]);
+                                } else {
+                                    path_buf.push_str(root.trim_end_matches('\\'));
+                                }
+                            } else {
+                                path_buf.push_str(root.trim_end_matches('\\'));
+                            }
+                        } else {
+                            path_buf.push_str(root.trim_end_matches('\\'));
+                        }
+                        path_buf.push('\\');
+                        path_buf.push_str(&name);
+
+                        if is_recycle_bin_path(&path_buf) {
+                            off += rec.record_length as usize;
+                            continue;
+                        }
+
+                        let is_dir = (rec.file_attributes & FILE_ATTRIBUTE_DIRECTORY) != 0;
+
+                        let kind = if is_delete || is_rename_old {
+                            crate::changes::ChangeKind::Deleted
+                        } else if is_rename_new || is_create {
+                            crate::changes::ChangeKind::Added
+                        } else if (reason & (USN_REASON_DATA_EXTEND | USN_REASON_DATA_OVERWRITE))
+                            != 0
+                        {
+                            crate::changes::ChangeKind::Modified
+                        } else {
+                            off += rec.record_length as usize;
+                            continue;
+                        };
+
+                        if is_dir && matches!(kind, crate::changes::ChangeKind::Added) {
+                            new_dirs.push((file_ref, Arc::new(path_buf.clone())));
+                        }
+
+                        let (size, mtime) = get_file_info_fast(&path_buf)
+                            .map(|s| (s.size, s.mtime))
+                            .unwrap_or((0, 0.0));
+
+                        callback(crate::changes::IndexChange {
+                            drive,
+                            kind,
+                            path: path_buf,
+                            size,
+                            is_dir,
+                            mtime,
+                        });
+                    }
+                }
+            }
+
+            off += rec.record_length as usize;
+        }
+
+        if !new_dirs.is_empty() {
+            insert_dir_cache_entries(drive, jd.usn_journal_id, new_dirs);
+        }
+
+        start_usn = next_usn;
+        save_index_meta(drive, volume_serial, start_usn);
+    }
+
+    unsafe { CloseHandle(h) };
+}
+
 unsafe fn scan_usn_journal_quick(
     h: windows_sys::Win32::Foundation::HANDLE,
     jd: &USN_JOURNAL_DATA_V0,
@@ -1320,6 +2174,10 @@ unsafe fn scan_usn_journal_quick(
                                 parent_ref: rec.parent_file_reference_number & 0xFFFFFFFFFFFF,
                                 is_dir: true,
                                 file_ref: rec.file_reference_number & 0xFFFFFFFFFFFF,
+                                is_reparse: (rec.file_attributes & FILE_ATTRIBUTE_REPARSE_POINT) != 0,
+                                reparse_target: None,
+                                size: 0,
+                                timestamp: 0.0,
                             });
                         }
                     }
@@ -1416,11 +2274,24 @@ fn scan_usn_journal_all(drive: char) -> Result<Vec<MftRecord>, Box<dyn std::erro
                     if let Ok(name) = String::from_utf16(slice) {
                         let fc = name.as_bytes().first().copied().unwrap_or(b'$');
                         if fc != b'$' && fc != b'.' {
+                            let is_reparse = (rec.file_attributes & FILE_ATTRIBUTE_REPARSE_POINT) != 0;
+                            let file_ref = rec.file_reference_number & 0xFFFFFFFFFFFF;
+                            // 只在真遇到重解析点时才多开一次句柄读目标——绝大多数记录都不是，不想拖慢整盘扫描
+                            let reparse_target = if is_reparse {
+                                read_reparse_point_by_ref(h, file_ref)
+                            } else {
+                                None
+                            };
+
                             records.push(MftRecord {
                                 filename: name,
                                 parent_ref: rec.parent_file_reference_number & 0xFFFFFFFFFFFF,
                                 is_dir: (rec.file_attributes & FILE_ATTRIBUTE_DIRECTORY) != 0,
-                                file_ref: rec.file_reference_number & 0xFFFFFFFFFFFF,
+                                file_ref,
+                                is_reparse,
+                                reparse_target,
+                                size: 0,
+                                timestamp: filetime_to_unix_secs(rec.time_stamp as u64),
                             });
                         }
                     }
@@ -1440,6 +2311,7 @@ fn scan_and_pack(drive: char) -> Result<(Vec<u8>, usize), Box<dyn std::error::Er
 
     let skip_dirs = build_skip_dirs_set();
     let skip_exts = build_skip_exts_set();
+    let ignore_rules = IgnoreRules::build(&root);
 
     let mut p2c: FxHashMap<u64, Vec<usize>> = FxHashMap::default();
     p2c.reserve(records.len() / 8);
@@ -1481,13 +2353,15 @@ fn scan_and_pack(drive: char) -> Result<(Vec<u8>, usize), Box<dyn std::error::Er
                 let r = &records[i];
                 if r.is_dir {
                     let name_lower = r.filename.to_ascii_lowercase();
-                    if should_skip_dir(&name_lower, &skip_dirs) {
+                    path_buf.clear();
+                    path_buf.push_str(parent_trimmed);
+                    path_buf.push('\\');
+                    path_buf.push_str(&r.filename);
+                    if should_skip_dir(&name_lower, &skip_dirs)
+                        || ignore_rules.is_extra_ignored(&path_buf, true)
+                    {
                         skip.insert(r.file_ref);
                     } else {
-                        path_buf.clear();
-                        path_buf.push_str(parent_trimmed);
-                        path_buf.push('\\');
-                        path_buf.push_str(&r.filename);
                         paths.insert(r.file_ref, Arc::new(path_buf.clone()));
                     }
                     queue.push_back(r.file_ref);
@@ -1512,13 +2386,25 @@ fn scan_and_pack(drive: char) -> Result<(Vec<u8>, usize), Box<dyn std::error::Er
             path.push('\\');
             path.push_str(&r.filename);
 
+            if !r.is_dir && ignore_rules.is_extra_ignored(&path, false) {
+                return None;
+            }
+
             let ext = if r.is_dir {
                 String::new()
             } else {
                 get_ext_lower(&r.filename)
             };
 
-            Some((r.filename.clone(), path, Arc::clone(parent), ext, r.is_dir))
+            // USN_RECORD_V2.TimeStamp 已经免费给了写入时间，不用再查一次；
+            // 大小 MftRecord 里没有免费来源，只能在这里按真实路径补一次 GetFileAttributesExW
+            let size = if r.is_dir {
+                0
+            } else {
+                get_file_info_fast(&path).map(|s| s.size).unwrap_or(0)
+            };
+
+            Some((r.filename.clone(), path, Arc::clone(parent), ext, r.is_dir, size, r.timestamp))
         })
         .collect();
 
@@ -1526,19 +2412,22 @@ fn scan_and_pack(drive: char) -> Result<(Vec<u8>, usize), Box<dyn std::error::Er
 
     let total_size: usize = items
         .iter()
-        .map(|(name, path, parent, ext, _)| 24 + name.len() + path.len() + parent.len() + ext.len())
+        .map(|(name, path, parent, ext, ..)| 24 + name.len() + path.len() + parent.len() + ext.len())
         .sum();
 
-    let mut data = Vec::with_capacity(total_size);
+    // data[0] 是格式版本号：v1（隐式）的 size/time 字段恒为 0，v2 开始这两个字段是真实值，
+    // 消费方看到版本号不是自己认识的旧值时就知道不能再假设它们是占位 0
+    let mut data = Vec::with_capacity(1 + total_size);
+    data.push(SCAN_PACK_FORMAT_VERSION);
 
-    for (filename, path, parent, ext, is_dir) in items {
+    for (filename, path, parent, ext, is_dir, size, timestamp) in items {
         data.push(if is_dir { 1 } else { 0 });
         data.extend(&(filename.len() as u16).to_le_bytes());
         data.extend(&(path.len() as u16).to_le_bytes());
         data.extend(&(parent.len() as u16).to_le_bytes());
         data.push(ext.len() as u8);
-        data.extend(&0u64.to_le_bytes());
-        data.extend(&0f64.to_le_bytes());
+        data.extend(&size.to_le_bytes());
+        data.extend(&timestamp.to_le_bytes());
         data.extend(filename.as_bytes());
         data.extend(path.as_bytes());
         data.extend(parent.as_bytes());
@@ -1548,89 +2437,944 @@ fn scan_and_pack(drive: char) -> Result<(Vec<u8>, usize), Box<dyn std::error::Er
     Ok((data, count))
 }
 
-// ============== 搜索索引 FFI 导出 ==============
+// ============== 卷文件系统探测 ==============
 
-/// 内部函数：初始化搜索索引（供内部调用）
-pub fn init_search_index_internal(drive: char) -> bool {
-    let drive = drive.to_ascii_uppercase();
+/// 记录每个盘符上次成功扫描时的 USN Journal ID，用于检测日志重建（journal 被重新创建后 ID 会变化）
+static LAST_JOURNAL_ID: LazyLock<RwLock<FxHashMap<char, u64>>> =
+    LazyLock::new(|| RwLock::new(FxHashMap::default()));
 
-    // 检查是否已初始化
-    {
-        let indices = SEARCH_INDICES.read();
-        if indices.contains_key(&drive) {
-            return true;
+/// 查询卷的文件系统名称（如 "NTFS"、"FAT32"、"exFAT"），查询失败时返回空字符串
+fn get_volume_filesystem(drive: char) -> String {
+    use windows_sys::Win32::Storage::FileSystem::GetVolumeInformationW;
+
+    let root: Vec<u16> = format!("{}:\\", drive)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut fs_name_buf = [0u16; 32];
+
+    unsafe {
+        let ok = GetVolumeInformationW(
+            root.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            fs_name_buf.as_mut_ptr(),
+            fs_name_buf.len() as u32,
+        );
+
+        if ok == 0 {
+            return String::new();
         }
+
+        let len = fs_name_buf.iter().position(|&c| c == 0).unwrap_or(fs_name_buf.len());
+        String::from_utf16_lossy(&fs_name_buf[..len])
     }
+}
 
-    // 先尝试从磁盘加载已持久化的索引，避免每次启动都重建
-    let index_path = format!("{}:\\.search_index.bin", drive);
-    if Path::new(&index_path).exists() {
-        let index = Arc::new(SearchIndex::new());
-        match index.load_from_file(Path::new(&index_path)) {
-            Ok(_) => {
-                log::info!("✅ 成功从磁盘加载索引: {}", index_path);
-                SEARCH_INDICES.write().insert(drive, index);
-                return true;
-            }
-            Err(e) => {
-                log::warn!("⚠️ 加载磁盘索引失败，将执行全盘重建: {} - {}", index_path, e);
-            }
-        }
-    } else {
-        log::info!("ℹ️ 索引文件不存在，将执行首次构建: {}", index_path);
+/// 把一条完整路径哈希成稳定的伪文件引用号：没有真实 file_ref 的场景（非 NTFS 卷的目录遍历）
+/// 靠它冒充 MFT 的文件引用号，这样 `build_path_map`/`init_search_index_internal` 里那条
+/// 只认 `MftRecord`/`file_ref`/`parent_ref` 的流水线不用关心背后到底是不是真的 MFT 记录
+fn pseudo_file_ref(path: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    path.to_ascii_lowercase().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 卷扫描抽象：把"拿到卷上所有文件记录"和"拿到自某个 USN 以来的变化"从 NTFS USN Journal 的
+/// 具体实现里抽出来。`init_search_index_internal` 按卷的文件系统名字选用哪个实现，
+/// 下游的 `build_path_map`、记录转 `IndexedItem` 的流水线完全不用关心背后是哪一种
+trait VolumeScanner {
+    fn scan_all(&self) -> Result<Vec<MftRecord>, Box<dyn std::error::Error>>;
+    fn changes_since(&self, last_usn: i64) -> Result<Vec<FileChange>, Box<dyn std::error::Error>>;
+}
+
+/// NTFS 卷：现有 USN Journal 实现原样包一层，行为不变
+struct UsnScanner {
+    drive: char,
+}
+
+impl VolumeScanner for UsnScanner {
+    fn scan_all(&self) -> Result<Vec<MftRecord>, Box<dyn std::error::Error>> {
+        scan_usn_journal_all(self.drive)
     }
 
-    // 扫描并构建索引
-    log::info!("📊 {} 盘开始扫描 USN Journal...", drive);
-    let start_time = std::time::Instant::now();
-    
-    let records = match scan_usn_journal_all(drive) {
-        Ok(r) => {
-            log::info!("✅ {} 盘扫描完成：{} 条记录，耗时 {:.2}秒", drive, r.len(), start_time.elapsed().as_secs_f64());
-            r
-        },
-        Err(e) => {
-            log::error!("❌ {} 盘扫描失败: {:?}", drive, e);
-            return false;
-        }
-    };
+    fn changes_since(&self, last_usn: i64) -> Result<Vec<FileChange>, Box<dyn std::error::Error>> {
+        get_changes_since(self.drive, last_usn)
+    }
+}
 
-    let root = format!("{}:\\", drive);
-    let skip_dirs = build_skip_dirs_set();
-    let skip_exts = build_skip_exts_set();
-    
-    log::info!("🔧 {} 盘开始构建路径映射...", drive);
+/// 没有 USN Journal 的卷（exFAT/FAT32/网络映射盘等）：用 `FindFirstFileW`/`FindNextFileW`
+/// 递归遍历代替 MFT 枚举，`file_ref`/`parent_ref` 全部用 [`pseudo_file_ref`] 哈希路径得到，
+/// 根目录固定给 5（和 NTFS 根目录的 MFT 文件引用号约定一致），这样 `build_path_map` 不用改
+struct DirWalkScanner {
+    drive: char,
+}
 
-    // 构建路径映射
-    let mut p2c: FxHashMap<u64, Vec<usize>> = FxHashMap::default();
-    p2c.reserve(records.len() / 8);
-    for (i, r) in records.iter().enumerate() {
-        p2c.entry(r.parent_ref).or_default().push(i);
+impl VolumeScanner for DirWalkScanner {
+    fn scan_all(&self) -> Result<Vec<MftRecord>, Box<dyn std::error::Error>> {
+        let root = format!("{}:\\", self.drive);
+        let mut records = Vec::new();
+        walk_dir_into_records(&root, 5, &mut records);
+        Ok(records)
     }
 
-    let mut paths: FxHashMap<u64, Arc<String>> = FxHashMap::default();
-    paths.reserve(records.len() / 4);
-    let mut skip: FxHashSet<u64> = FxHashSet::default();
-    skip.reserve(records.len() / 16);
+    /// 没有变更日志可用：增量轮询在这类卷上没有意义，调用方（`apply_usn_to_index` 等）
+    /// 应该靠定期全量 `scan_all` 重扫来发现变化，而不是指望这里吐出增量
+    fn changes_since(&self, _last_usn: i64) -> Result<Vec<FileChange>, Box<dyn std::error::Error>> {
+        Ok(Vec::new())
+    }
+}
 
-    paths.insert(5, Arc::new(root));
-    let mut queue = VecDeque::with_capacity(8000);
-    queue.push_back(5u64);
+/// 用 Win32 `FindFirstFileW`/`FindNextFileW` 递归枚举 `dir`（已知其 `dir_ref`），
+/// 把每个子项追加成一条 `MftRecord`，目录则继续下钻。重解析点只记标记和目标，不下钻——
+/// 和 NTFS 路径里 `build_path_map` 对 junction 的处理保持一致，避免环形 junction 卡死
+fn walk_dir_into_records(dir: &str, dir_ref: u64, out: &mut Vec<MftRecord>) {
+    use windows_sys::Win32::Storage::FileSystem::{
+        FindClose, FindFirstFileW, FindNextFileW, FILE_ATTRIBUTE_DIRECTORY as WIN_FILE_ATTRIBUTE_DIRECTORY,
+        WIN32_FIND_DATAW,
+    };
 
-    let mut path_buf = String::with_capacity(512);
+    let pattern = format!("{}\\*", dir.trim_end_matches('\\'));
+    let wide: Vec<u16> = OsStr::new(&to_extended_length_path(&pattern))
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
 
-    while let Some(pid) = queue.pop_front() {
-        if skip.contains(&pid) {
-            if let Some(cs) = p2c.get(&pid) {
-                for &i in cs {
-                    skip.insert(records[i].file_ref);
-                    queue.push_back(records[i].file_ref);
-                }
+    let mut find_data = unsafe { std::mem::zeroed::<WIN32_FIND_DATAW>() };
+    let handle = unsafe { FindFirstFileW(wide.as_ptr(), &mut find_data) };
+    if handle == windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE {
+        return;
+    }
+
+    let mut subdirs: Vec<(String, u64)> = Vec::new();
+
+    loop {
+        let name_len = find_data
+            .cFileName
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(find_data.cFileName.len());
+        let name = String::from_utf16_lossy(&find_data.cFileName[..name_len]);
+
+        if name != "." && name != ".." {
+            let is_dir = (find_data.dwFileAttributes & WIN_FILE_ATTRIBUTE_DIRECTORY) != 0;
+            let is_reparse = (find_data.dwFileAttributes & FILE_ATTRIBUTE_REPARSE_POINT) != 0;
+            let path = format!("{}\\{}", dir.trim_end_matches('\\'), name);
+            let file_ref = pseudo_file_ref(&path);
+
+            let reparse_target = if is_reparse {
+                read_reparse_point_raw(&path)
+            } else {
+                None
+            };
+
+            if is_dir && !is_reparse {
+                subdirs.push((path, file_ref));
             }
-            continue;
+
+            // FindFirstFileW 的 WIN32_FIND_DATAW 本来就带着大小和写入时间，不用再多一次系统调用
+            let size = if is_dir {
+                0
+            } else {
+                ((find_data.nFileSizeHigh as u64) << 32) | find_data.nFileSizeLow as u64
+            };
+            let write_time = ((find_data.ftLastWriteTime.dwHighDateTime as u64) << 32)
+                | find_data.ftLastWriteTime.dwLowDateTime as u64;
+
+            out.push(MftRecord {
+                filename: name,
+                parent_ref: dir_ref,
+                is_dir,
+                file_ref,
+                is_reparse,
+                reparse_target,
+                size,
+                timestamp: filetime_to_unix_secs(write_time),
+            });
         }
 
-        let parent_path_owned = match paths.get(&pid) {
+        if unsafe { FindNextFileW(handle, &mut find_data) } == 0 {
+            break;
+        }
+    }
+
+    unsafe {
+        FindClose(handle);
+    }
+
+    for (path, file_ref) in subdirs {
+        walk_dir_into_records(&path, file_ref, out);
+    }
+}
+
+// ============== ISO9660 虚拟卷扫描器 ==============
+
+const ISO_SECTOR_SIZE: u64 = 2048;
+/// 主卷描述符（Primary Volume Descriptor）固定位于第 16 扇区，这是 ISO9660 规定的常量
+const ISO_PVD_SECTOR: u64 = 16;
+
+/// 把 `.iso` 镜像当成一个只读虚拟卷扫描：不挂载，直接按 2048 字节逻辑扇区读取文件，
+/// 解析卷描述符找到根目录 extent，再深度优先走目录记录。`file_ref`/`parent_ref` 直接用
+/// 目录记录的起始 LBA（逻辑块号），天然唯一，不需要像 `DirWalkScanner` 那样额外哈希
+struct IsoVolumeScanner {
+    /// 镜像文件在宿主文件系统上的路径
+    image_path: String,
+}
+
+/// 一条已解析的 ISO9660 目录记录
+struct IsoDirRecord {
+    name: String,
+    extent_lba: u32,
+    data_len: u32,
+    is_dir: bool,
+    /// Rock Ridge `SL` 条目给出的符号链接目标；非空则该记录被当成一个重解析点处理
+    rr_symlink: Option<String>,
+}
+
+impl VolumeScanner for IsoVolumeScanner {
+    fn scan_all(&self) -> Result<Vec<MftRecord>, Box<dyn std::error::Error>> {
+        let records = walk_iso_image(&self.image_path)?;
+        let mut out = Vec::with_capacity(records.len() + 1);
+        out.push(MftRecord {
+            filename: String::new(),
+            parent_ref: 0,
+            is_dir: true,
+            file_ref: 5,
+            is_reparse: false,
+            reparse_target: None,
+            size: 0,
+            timestamp: 0.0,
+        });
+        for (parent_ref, entry) in records {
+            let size = if entry.is_dir { 0 } else { entry.data_len as u64 };
+            out.push(MftRecord {
+                filename: entry.name,
+                parent_ref,
+                is_dir: entry.is_dir,
+                file_ref: entry.extent_lba as u64,
+                is_reparse: entry.rr_symlink.is_some(),
+                reparse_target: entry.rr_symlink,
+                size,
+                // ISO9660 目录记录里的 recording date/time 字段格式和 FILETIME 不同，
+                // 这里暂不解析，留 0.0——和镜像内容的"是否是目录"一样不影响搜索可用性
+                timestamp: 0.0,
+            });
+        }
+        Ok(out)
+    }
+
+    /// ISO 镜像是只读静态文件，没有变更日志；发现内容变化只能靠重新 `scan_all` 全量重扫
+    fn changes_since(&self, _last_usn: i64) -> Result<Vec<FileChange>, Box<dyn std::error::Error>> {
+        Ok(Vec::new())
+    }
+}
+
+/// 深度优先走遍整个 ISO9660 镜像的目录树，返回每条记录及其父记录的 extent LBA（`parent_ref`）。
+/// `scan_all`（喂给通用 `VolumeScanner` 流水线）和 [`index_iso_image`]（保留真实大小直接建索引）
+/// 共用这同一次遍历，避免重复实现两套扇区解析逻辑
+fn walk_iso_image(image_path: &str) -> Result<Vec<(u64, IsoDirRecord)>, Box<dyn std::error::Error>> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(image_path)?;
+
+    let read_sector = |file: &mut fs::File, lba: u64| -> std::io::Result<Vec<u8>> {
+        let mut buf = vec![0u8; ISO_SECTOR_SIZE as usize];
+        file.seek(SeekFrom::Start(lba * ISO_SECTOR_SIZE))?;
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    };
+
+    // 扫描卷描述符序列（从第 16 扇区开始，直到 Volume Descriptor Set Terminator，type=255），
+    // 记录主卷描述符的根目录记录；若遇到 Joliet 增补卷描述符（type=2，且转义序列匹配
+    // `%/@`/`%/C`/`%/E` 之一），优先改用它的根目录记录——它的文件名是大端 UTF-16，能表示
+    // ISO9660 Level 1 名字塞不下的长文件名/中文名
+    let mut root_dir_record_buf: Option<Vec<u8>> = None;
+    let mut use_joliet = false;
+    let mut sector = ISO_PVD_SECTOR;
+    loop {
+        let buf = read_sector(&mut file, sector)?;
+        if &buf[1..6] != b"CD001" {
+            return Err("不是有效的 ISO9660 镜像：缺少 CD001 标识".into());
+        }
+        let descriptor_type = buf[0];
+        if descriptor_type == 255 {
+            break;
+        }
+        if descriptor_type == 1 && root_dir_record_buf.is_none() {
+            root_dir_record_buf = Some(buf[156..156 + 34].to_vec());
+        } else if descriptor_type == 2 {
+            let escape = &buf[88..120];
+            let is_joliet = escape.starts_with(&[0x25, 0x2F, 0x40])
+                || escape.starts_with(&[0x25, 0x2F, 0x43])
+                || escape.starts_with(&[0x25, 0x2F, 0x45]);
+            if is_joliet {
+                root_dir_record_buf = Some(buf[156..156 + 34].to_vec());
+                use_joliet = true;
+            }
+        }
+        sector += 1;
+    }
+
+    let root_buf = root_dir_record_buf.ok_or("ISO9660 镜像中未找到主卷描述符")?;
+    let root_lba = u32::from_le_bytes([root_buf[2], root_buf[3], root_buf[4], root_buf[5]]);
+    let root_len = u32::from_le_bytes([root_buf[10], root_buf[11], root_buf[12], root_buf[13]]);
+
+    let mut out = Vec::new();
+    let mut queue: VecDeque<(u32, u32, u64)> = VecDeque::new();
+    queue.push_back((root_lba, root_len, 5));
+
+    while let Some((extent_lba, data_len, parent_ref)) = queue.pop_back() {
+        let sectors_needed = (data_len as u64).div_ceil(ISO_SECTOR_SIZE).max(1);
+        let mut extent_buf = Vec::with_capacity((sectors_needed * ISO_SECTOR_SIZE) as usize);
+        for i in 0..sectors_needed {
+            extent_buf.extend_from_slice(&read_sector(&mut file, extent_lba as u64 + i)?);
+        }
+
+        for entry in parse_iso_directory_entries(&extent_buf, use_joliet) {
+            let file_ref = entry.extent_lba as u64;
+            let is_dir = entry.is_dir;
+            let is_reparse = entry.rr_symlink.is_some();
+            if is_dir && !is_reparse {
+                queue.push_back((entry.extent_lba, entry.data_len, file_ref));
+            }
+            out.push((parent_ref, entry));
+        }
+    }
+
+    Ok(out)
+}
+
+/// 把一个 `.iso` 镜像索引进 [`ISO_INDICES`]：内部路径全部用镜像文件自身的路径作前缀
+/// （比如 `D:\games\foo.iso\bin\setup.exe`），这样普通搜索结果里能看到镜像内部的内容，
+/// 同时一眼就能看出它来自哪个镜像文件。大小直接取自 ISO 目录记录的 `data_len`，不需要
+/// 像真实卷那样再调一次 `get_file_info_fast`——镜像是静态文件，这个数字本来就是准的
+pub fn index_iso_image(image_path: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let records = walk_iso_image(image_path)?;
+
+    let mut paths: FxHashMap<u64, Arc<String>> = FxHashMap::default();
+    paths.insert(5, Arc::new(image_path.to_string()));
+
+    let mut items = Vec::with_capacity(records.len());
+    for (parent_ref, entry) in &records {
+        let parent = match paths.get(parent_ref) {
+            Some(p) => Arc::clone(p),
+            None => continue,
+        };
+        let path = format!("{}\\{}", parent.trim_end_matches('\\'), entry.name);
+        let file_ref = entry.extent_lba as u64;
+        if entry.is_dir {
+            paths.insert(file_ref, Arc::new(path.clone()));
+        }
+
+        let is_symlink = entry.rr_symlink.is_some();
+        items.push(IndexedItem {
+            name: entry.name.clone(),
+            name_lower: String::new(),
+            path,
+            file_ref,
+            parent_ref: *parent_ref,
+            size: if entry.is_dir { 0 } else { entry.data_len as u64 },
+            is_dir: entry.is_dir,
+            mtime: 0.0,
+            extension: String::new(),
+            file_type: search_index::FileType::classify(entry.is_dir, is_symlink),
+            link_target: entry.rr_symlink.clone(),
+            hard_links: 1,
+            ctime: 0.0,
+            atime: 0.0,
+            ctime_raw: 0,
+            atime_raw: 0,
+            // ISO9660 目录记录里没有 Windows 属性位这一说，镜像内容本来就是只读静态的
+            attrs: 0,
+        });
+    }
+
+    log::info!("✅ ISO 镜像扫描完成：{} 个项目 ({})", items.len(), image_path);
+
+    let index = Arc::new(SearchIndex::new());
+    index.build(items);
+    ISO_INDICES.write().insert(image_path.to_string(), index);
+    Ok(true)
+}
+
+/// 解析一段目录 extent 里的所有目录记录（跳过 `.`/`..` 自引用项），
+/// 同时处理 Joliet 的大端 UTF-16 文件名和 Rock Ridge 的 `NM`/`PX`/`SL` System Use 条目
+fn parse_iso_directory_entries(extent: &[u8], use_joliet: bool) -> Vec<IsoDirRecord> {
+    let mut entries = Vec::new();
+    let mut offset = 0usize;
+
+    while offset < extent.len() {
+        let record_len = extent[offset] as usize;
+        if record_len == 0 {
+            // 记录长度为 0 表示本扇区剩余部分是填充，跳到下一扇区边界继续
+            let consumed_in_sector = offset % ISO_SECTOR_SIZE as usize;
+            if consumed_in_sector == 0 {
+                break;
+            }
+            offset += ISO_SECTOR_SIZE as usize - consumed_in_sector;
+            continue;
+        }
+        if offset + record_len > extent.len() {
+            break;
+        }
+        // ISO9660 目录记录固定部分至少 34 字节（到 `Length of File Identifier` 后的
+        // 第一个文件名字节为止）；截断/损坏的镜像可能把 `record_len` 写成一个更小的值，
+        // 继续按固定偏移量索引会越界 panic，这里直接跳过这条记录
+        if record_len < 34 {
+            offset += record_len;
+            continue;
+        }
+
+        let record = &extent[offset..offset + record_len];
+        let extent_lba = u32::from_le_bytes([record[2], record[3], record[4], record[5]]);
+        let data_len = u32::from_le_bytes([record[10], record[11], record[12], record[13]]);
+        let flags = record[25];
+        let is_dir = (flags & 0x02) != 0;
+        // 同样防止文件标识符长度越过记录自身的边界
+        let len_fi = (record[32] as usize).min(record_len - 33);
+
+        offset += record_len;
+
+        if len_fi == 1 && (record[33] == 0 || record[33] == 1) {
+            // `.`（0x00）或 `..`（0x01）自引用项，调用方已经知道父子关系，跳过
+            continue;
+        }
+
+        let name_bytes = &record[33..33 + len_fi];
+        let mut name = if use_joliet {
+            decode_utf16_be(name_bytes)
+        } else {
+            String::from_utf8_lossy(name_bytes).to_string()
+        };
+        // 去掉纯 ISO9660 名字上的版本号后缀（如 `FILE.TXT;1`）
+        if let Some(pos) = name.rfind(";1") {
+            if pos + 2 == name.len() {
+                name.truncate(pos);
+            }
+        }
+
+        // System Use 区域紧跟在文件标识符之后（若 len_fi 为偶数，还有 1 字节填充），
+        // 一直延伸到记录末尾——Rock Ridge 的 NM/PX/SL 条目就藏在这里
+        let su_start = 33 + len_fi + if len_fi % 2 == 0 { 1 } else { 0 };
+        let (rr_name, rr_symlink) = if su_start < record.len() {
+            parse_rock_ridge_entries(&record[su_start..])
+        } else {
+            (None, None)
+        };
+
+        entries.push(IsoDirRecord {
+            name: rr_name.unwrap_or(name),
+            extent_lba,
+            data_len,
+            is_dir,
+            rr_symlink,
+        });
+    }
+
+    entries
+}
+
+/// 扫描 System Use 区域里的 SUSP 条目，提取 Rock Ridge 的 POSIX 名字（`NM`）和符号链接目标（`SL`）。
+/// `PX`（POSIX 权限/类型）目前只是被跳过——`is_dir` 已经从 ISO9660 原生的目录标志位拿到，
+/// 足够满足索引对"是不是目录"的需求，不需要再解出完整的 st_mode
+fn parse_rock_ridge_entries(su: &[u8]) -> (Option<String>, Option<String>) {
+    let mut name = String::new();
+    let mut symlink_parts: Vec<String> = Vec::new();
+    let mut offset = 0usize;
+
+    while offset + 4 <= su.len() {
+        let signature = &su[offset..offset + 2];
+        let len = su[offset + 2] as usize;
+        if len < 4 || offset + len > su.len() {
+            break;
+        }
+        let data = &su[offset + 4..offset + len];
+
+        match signature {
+            b"NM" if !data.is_empty() => {
+                // data[0] 是续传标志，bit0=1 表示名字在下一条 NM 记录中继续
+                name.push_str(&String::from_utf8_lossy(&data[1..]));
+            }
+            b"SL" if !data.is_empty() => {
+                // 简化实现：只取组件记录里的字面内容，不单独处理 ROOT/CURRENT/PARENT 等特殊组件标志
+                let mut comp_offset = 1usize; // data[0] 是 SL 的整体续传标志
+                while comp_offset + 2 <= data.len() {
+                    let comp_len = data[comp_offset + 1] as usize;
+                    if comp_offset + 2 + comp_len > data.len() {
+                        break;
+                    }
+                    let comp = &data[comp_offset + 2..comp_offset + 2 + comp_len];
+                    symlink_parts.push(String::from_utf8_lossy(comp).to_string());
+                    comp_offset += 2 + comp_len;
+                }
+            }
+            _ => {}
+        }
+
+        offset += len;
+    }
+
+    let name = if name.is_empty() { None } else { Some(name) };
+    let symlink = if symlink_parts.is_empty() {
+        None
+    } else {
+        Some(symlink_parts.join("/"))
+    };
+    (name, symlink)
+}
+
+/// Joliet 文件名按 ISO 10646(UCS-2) 大端编码，这里只做到 BMP 范围内的直接转换
+fn decode_utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+// ============== 索引持久化校验 token ==============
+
+/// 持久化索引的有效性校验信息：卷序列号变化（换盘/重新格式化）或缺失都意味着
+/// 磁盘上的索引已不可信，必须触发全量重建而不是增量应用
+#[derive(Serialize, Deserialize)]
+struct IndexMeta {
+    volume_serial: u32,
+    last_usn: i64,
+}
+
+fn index_meta_path(drive: char) -> String {
+    format!("{}:\\.search_index.meta", drive)
+}
+
+fn save_index_meta(drive: char, volume_serial: u32, last_usn: i64) {
+    let meta = IndexMeta { volume_serial, last_usn };
+    if let Ok(bytes) = bincode::serialize(&meta) {
+        let _ = fs::write(index_meta_path(drive), bytes);
+    }
+}
+
+fn load_index_meta(drive: char) -> Option<IndexMeta> {
+    let bytes = fs::read(index_meta_path(drive)).ok()?;
+    bincode::deserialize(&bytes).ok()
+}
+
+/// 查询卷序列号（格式化卷时会变化），用作持久化索引的有效性 token
+fn get_volume_serial(drive: char) -> u32 {
+    use windows_sys::Win32::Storage::FileSystem::GetVolumeInformationW;
+
+    let root: Vec<u16> = format!("{}:\\", drive)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut serial: u32 = 0;
+
+    unsafe {
+        let ok = GetVolumeInformationW(
+            root.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            &mut serial,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            0,
+        );
+
+        if ok == 0 {
+            return 0;
+        }
+    }
+
+    serial
+}
+
+/// 把自 `since_usn` 以来的 USN 变化直接应用到内存索引中，返回应用后的最新 USN
+fn apply_usn_delta_to_index(drive: char, index: &SearchIndex, since_usn: i64) -> i64 {
+    let current_usn = get_current_usn(drive as u16);
+    if current_usn <= since_usn {
+        return current_usn;
+    }
+
+    let changes = get_usn_changes(drive as u16, since_usn);
+    if changes.count == 0 {
+        return current_usn;
+    }
+
+    let changes_vec = unsafe { std::slice::from_raw_parts(changes.changes, changes.count) };
+    let mut added = 0;
+    let mut deleted = 0;
+
+    for change in changes_vec {
+        if change.path_ptr.is_null() {
+            continue;
+        }
+        let path_bytes = unsafe { std::slice::from_raw_parts(change.path_ptr, change.path_len) };
+        let path = String::from_utf8_lossy(path_bytes).to_string();
+        if path.is_empty() {
+            continue;
+        }
+
+        if change.action == 0 || change.action == 4 {
+            if index.remove_file_by_path(&path) {
+                deleted += 1;
+            }
+        } else if change.action == 1 || change.action == 2 || change.action == 3 {
+            if let Ok(metadata) = fs::metadata(&path) {
+                use std::hash::{Hash, Hasher};
+                use std::os::windows::fs::MetadataExt;
+
+                let is_symlink = fs::symlink_metadata(&path)
+                    .map(|m| m.file_type().is_symlink())
+                    .unwrap_or(false);
+
+                let filename = Path::new(&path)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                path.hash(&mut hasher);
+                let file_ref = hasher.finish();
+
+                let parent_path = Path::new(&path)
+                    .parent()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let mut parent_hasher = std::collections::hash_map::DefaultHasher::new();
+                parent_path.hash(&mut parent_hasher);
+                let parent_ref = parent_hasher.finish();
+
+                let link_target = if is_symlink {
+                    fs::read_link(&path).ok().map(|p| p.to_string_lossy().to_string())
+                } else {
+                    None
+                };
+
+                index.add_file(IndexedItem {
+                    name: filename.clone(),
+                    name_lower: filename.to_lowercase(),
+                    path: path.clone(),
+                    file_ref,
+                    parent_ref,
+                    size: metadata.len(),
+                    is_dir: metadata.is_dir(),
+                    mtime: metadata
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs_f64())
+                        .unwrap_or(0.0),
+                    extension: String::new(),
+                    file_type: search_index::FileType::classify(metadata.is_dir(), is_symlink),
+                    link_target,
+                    hard_links: 1,
+                    ctime: 0.0,
+                    atime: 0.0,
+                    ctime_raw: 0,
+                    atime_raw: 0,
+                    attrs: metadata.file_attributes(),
+                });
+                added += 1;
+            }
+        }
+    }
+
+    log::info!("📑 {} 盘加载后增量应用: +{} -{}", drive, added, deleted);
+    free_change_list(changes);
+    current_usn
+}
+
+/// 对外的真正"增量更新"入口：读取该盘常驻索引自己记录的 `last_usn`，把这之后的 USN 变化直接
+/// 应用进 `SEARCH_INDICES` 里的活索引（而不是像 `init_search_index_internal` 那样只在加载时补一次），
+/// 并把新的 `last_usn` 写回 `.search_index.meta`，让下一次调用能接着这次的位置继续。
+/// 调用方（宿主）应该在收到文件系统变化通知、或定期轮询时调用它，取代"全量重扫"。
+/// 返回应用后的最新 USN；盘未建索引、或查询 USN 失败时返回 -1
+#[no_mangle]
+pub extern "C" fn apply_usn_to_index(drive_letter: u16) -> i64 {
+    let drive = (drive_letter as u8 as char).to_ascii_uppercase();
+
+    let index = {
+        let indices = SEARCH_INDICES.read();
+        match indices.get(&drive) {
+            Some(idx) => Arc::clone(idx),
+            None => return -1,
+        }
+    };
+
+    let volume_serial = get_volume_serial(drive);
+    let since_usn = load_index_meta(drive)
+        .filter(|m| m.volume_serial == volume_serial)
+        .map(|m| m.last_usn)
+        .unwrap_or(0);
+
+    let new_last_usn = apply_usn_delta_to_index(drive, &index, since_usn);
+    save_index_meta(drive, volume_serial, new_last_usn);
+    new_last_usn
+}
+
+// ============== 搜索索引 FFI 导出 ==============
+
+/// 内部函数：初始化搜索索引（供内部调用）
+pub fn init_search_index_internal(drive: char) -> bool {
+    let drive = drive.to_ascii_uppercase();
+
+    // 检查是否已初始化
+    {
+        let indices = SEARCH_INDICES.read();
+        if indices.contains_key(&drive) {
+            return true;
+        }
+    }
+
+    // 按卷的文件系统选用扫描器：NTFS 走 USN Journal，没有 Journal 的卷（FAT32/exFAT/网络映射盘等）
+    // 走目录遍历兜底。两者都产出 MftRecord，后面统一喂给同一条 build_items_from_records 流水线
+    let filesystem = get_volume_filesystem(drive);
+    let is_ntfs = filesystem.eq_ignore_ascii_case("NTFS");
+
+    // 先尝试从磁盘加载已持久化的索引，避免每次启动都重建
+    let index_path = format!("{}:\\.search_index.bin", drive);
+    if Path::new(&index_path).exists() {
+        let index = Arc::new(SearchIndex::new());
+        match index.load_from_file(Path::new(&index_path)) {
+            Ok(_) => {
+                let current_serial = get_volume_serial(drive);
+                let meta = load_index_meta(drive);
+                let token_valid = meta
+                    .as_ref()
+                    .map(|m| m.volume_serial == current_serial)
+                    .unwrap_or(false);
+
+                if let Some(meta) = meta.filter(|_| token_valid) {
+                    if is_ntfs {
+                        log::info!(
+                            "✅ 成功从磁盘加载索引: {}，应用自 USN {} 以来的增量变化",
+                            index_path, meta.last_usn
+                        );
+                        let new_last_usn = apply_usn_delta_to_index(drive, &index, meta.last_usn);
+                        save_index_meta(drive, current_serial, new_last_usn);
+                    } else {
+                        // 非 NTFS 没有变更日志可查，USN 增量在这类卷上没有意义：改成对已索引的
+                        // 每一项重新 stat 一次 mtime，发现变化或文件已消失就原地更新/摘除，
+                        // 不需要整盘重新遍历
+                        let changed =
+                            index.refresh_with(|path| get_file_info_fast(path).map(|s| (s.size, s.mtime)));
+                        log::info!(
+                            "✅ 成功从磁盘加载索引: {}，增量刷新发现 {} 项变化",
+                            index_path, changed
+                        );
+                        save_index_meta(drive, current_serial, meta.last_usn);
+                    }
+                    let index_for_db = index.clone();
+                    SEARCH_INDICES.write().insert(drive, index);
+                    std::thread::spawn(move || sync_database_shadow(drive, &index_for_db));
+                    return true;
+                }
+
+                log::warn!(
+                    "⚠️ 持久化索引的校验 token 不匹配（卷序列号已变化），将执行全盘重建: {}",
+                    index_path
+                );
+            }
+            Err(e) => {
+                log::warn!("⚠️ 加载磁盘索引失败，将执行全盘重建: {} - {}", index_path, e);
+            }
+        }
+    } else {
+        log::info!("ℹ️ 索引文件不存在，将执行首次构建: {}", index_path);
+    }
+
+    let scanner: Box<dyn VolumeScanner> = if is_ntfs {
+        Box::new(UsnScanner { drive })
+    } else {
+        log::info!("ℹ️ {} 盘文件系统为 {:?}，非 NTFS，回退到目录遍历构建索引", drive, filesystem);
+        Box::new(DirWalkScanner { drive })
+    };
+
+    log::info!("📊 {} 盘开始扫描卷内容...", drive);
+    let start_time = std::time::Instant::now();
+
+    let records = match scanner.scan_all() {
+        Ok(r) => {
+            log::info!("✅ {} 盘扫描完成：{} 条记录，耗时 {:.2}秒", drive, r.len(), start_time.elapsed().as_secs_f64());
+            r
+        },
+        Err(e) => {
+            log::error!("❌ {} 盘扫描失败: {:?}", drive, e);
+            return false;
+        }
+    };
+
+    // 仅 NTFS 卷记录扫描时的 Journal ID：非 NTFS 没有 Journal，增量变更靠定期全量重扫发现
+    if is_ntfs {
+        let journal_id = get_usn_journal_id(drive as u16);
+        if journal_id != 0 {
+            LAST_JOURNAL_ID.write().insert(drive, journal_id);
+        }
+    }
+
+    log::info!("📝 {} 盘开始构建索引项...", drive);
+    let indexed_items = build_items_from_records(drive, &records);
+
+    // 创建索引
+    log::info!("🏗️ {} 盘开始创建搜索索引：{} 个项目", drive, indexed_items.len());
+    let index = Arc::new(SearchIndex::new());
+    index.build(indexed_items);
+
+    // 尝试持久化（写到驱动器根目录）
+    log::info!("💾 {} 盘保存索引到磁盘...", drive);
+    let _ = index.save_to_file(Path::new(&index_path));
+    let last_usn = if is_ntfs { get_current_usn(drive as u16) } else { 0 };
+    save_index_meta(drive, get_volume_serial(drive), last_usn);
+
+    let index_for_db = index.clone();
+    SEARCH_INDICES.write().insert(drive, index);
+    std::thread::spawn(move || sync_database_shadow(drive, &index_for_db));
+    log::info!("✅ {} 盘索引构建完成！", drive);
+    true
+}
+
+/// 把内存索引同步进 `database::Database` 这份 SQLite 影子拷贝：供 `search_files_db` 之类
+/// 需要并发只读/FTS5/分页查询的命令使用，不参与主搜索路径，失败不影响常驻内存索引可用性
+fn sync_database_shadow(drive: char, index: &SearchIndex) {
+    let db_path = database_path_for(drive);
+    let mut db = match database::Database::new(&db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            log::warn!("⚠️ {} 盘 SQLite 影子索引打开失败: {} - {}", drive, db_path, e);
+            return;
+        }
+    };
+
+    let entries: Vec<database::FileEntry> = index
+        .all_items()
+        .iter()
+        .map(|item| file_entry_from_indexed_item(item))
+        .collect();
+
+    let stats = match db.sync_drive(drive, &entries) {
+        Ok(stats) => {
+            log::info!(
+                "🗄️ {} 盘 SQLite 影子索引同步完成：+{} ~{} -{}",
+                drive, stats.added, stats.changed, stats.removed
+            );
+            stats
+        }
+        Err(e) => {
+            log::warn!("⚠️ {} 盘 SQLite 影子索引同步失败: {}", drive, e);
+            return;
+        }
+    };
+
+    // `open_reader`/`search_files_db` 要求库已经处于 WAL 模式才能看到这里写入的数据；
+    // `Database::new` 为了批量写入跑在 synchronous=OFF/journal_mode=MEMORY 下，必须在
+    // 写完之后切回来，否则读端永远读不到影子索引里的最新内容
+    if let Err(e) = db.restore_normal_mode() {
+        log::warn!("⚠️ {} 盘 SQLite 影子索引切换 WAL 模式失败: {}", drive, e);
+    }
+
+    // FTS5 全文重建和快照导出都是整表级别的操作，在 mtime 刷新之类的无变化轮询里反复
+    // 跑等于白白重做一遍，这里按 sync_drive 的统计结果门控：只有真的有增删改时才做
+    if stats.added == 0 && stats.changed == 0 && stats.removed == 0 {
+        log::debug!("🗄️ {} 盘本轮没有变化，跳过 FTS5 重建与快照导出", drive);
+        return;
+    }
+
+    if let Err(e) = db.build_fts() {
+        log::warn!("⚠️ {} 盘 FTS5 全文索引构建失败: {}", drive, e);
+    }
+
+    let snapshot_path = format!("{}:\\.search_index.snapshot", drive);
+    if let Err(e) = db.export_snapshot(&snapshot_path) {
+        log::warn!("⚠️ {} 盘快照导出失败: {}", drive, e);
+    }
+}
+
+/// `database.rs` 里 `Database` 相关 API 使用的每驱动器 SQLite 文件路径，与 `index_path`
+/// （bincode 格式的 `.search_index.bin`）并列存在于驱动器根目录
+pub(crate) fn database_path_for(drive: char) -> String {
+    format!("{}:\\.search_index.db", drive)
+}
+
+/// 把常驻内存的 `IndexedItem` 折算成 `database::FileEntry`：`parent_dir` 从 `path` 现算，
+/// 因为 `IndexedItem` 本身不存这一列；`file_type` 按变体逐一对应到 `database::FileType`
+fn file_entry_from_indexed_item(item: &search_index::IndexedItem) -> database::FileEntry {
+    let parent_dir = Path::new(&item.path)
+        .parent()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    let file_type = match item.file_type {
+        search_index::FileType::File => database::FileType::File,
+        search_index::FileType::Dir => database::FileType::Dir,
+        search_index::FileType::Symlink => database::FileType::SymLink,
+        search_index::FileType::CharDevice => database::FileType::CharDevice,
+        search_index::FileType::BlockDevice => database::FileType::BlockDevice,
+        search_index::FileType::Fifo => database::FileType::Pipe,
+        search_index::FileType::Socket => database::FileType::Socket,
+    };
+
+    database::FileEntry {
+        name: item.name.clone(),
+        name_lower: item.name_lower.clone(),
+        full_path: item.path.clone(),
+        parent_dir,
+        extension: item.extension.clone(),
+        size: item.size,
+        mtime: item.mtime,
+        file_type,
+        link_target: item.link_target.clone(),
+    }
+}
+
+/// 把 [`VolumeScanner::scan_all`] 产出的 `MftRecord` 列表转换成 `IndexedItem` 列表：
+/// 先用 `build_path_map` 同款的路径映射+跳过目录的 BFS 拼出每条记录的完整路径，
+/// 再并行调用 `get_file_info_fast` 补齐体积/时间戳等真实元数据。
+/// 只依赖 `parent_ref`/`file_ref`/`filename`/`is_dir` 和"根目录引用号为 5"这个约定，
+/// 不关心记录是来自 USN Journal 还是目录遍历——这正是 NTFS/非 NTFS 能共用同一条流水线的原因
+fn build_items_from_records(drive: char, records: &[MftRecord]) -> Vec<IndexedItem> {
+    let root = format!("{}:\\", drive);
+    let skip_dirs = build_skip_dirs_set();
+    let skip_exts = build_skip_exts_set();
+    let ignore_rules = IgnoreRules::build(&root);
+
+    log::info!("🔧 {} 盘开始构建路径映射...", drive);
+
+    let mut p2c: FxHashMap<u64, Vec<usize>> = FxHashMap::default();
+    p2c.reserve(records.len() / 8);
+    for (i, r) in records.iter().enumerate() {
+        p2c.entry(r.parent_ref).or_default().push(i);
+    }
+
+    let mut paths: FxHashMap<u64, Arc<String>> = FxHashMap::default();
+    paths.reserve(records.len() / 4);
+    let mut skip: FxHashSet<u64> = FxHashSet::default();
+    skip.reserve(records.len() / 16);
+
+    paths.insert(5, Arc::new(root));
+    let mut queue = VecDeque::with_capacity(8000);
+    queue.push_back(5u64);
+
+    let mut path_buf = String::with_capacity(512);
+
+    while let Some(pid) = queue.pop_front() {
+        if skip.contains(&pid) {
+            if let Some(cs) = p2c.get(&pid) {
+                for &i in cs {
+                    skip.insert(records[i].file_ref);
+                    queue.push_back(records[i].file_ref);
+                }
+            }
+            continue;
+        }
+
+        let parent_path_owned = match paths.get(&pid) {
             Some(p) => Arc::clone(p),
             None => continue,
         };
@@ -1641,13 +3385,15 @@ pub fn init_search_index_internal(drive: char) -> bool {
                 let r = &records[i];
                 if r.is_dir {
                     let name_lower = r.filename.to_ascii_lowercase();
-                    if should_skip_dir(&name_lower, &skip_dirs) {
+                    path_buf.clear();
+                    path_buf.push_str(parent_trimmed);
+                    path_buf.push('\\');
+                    path_buf.push_str(&r.filename);
+                    if should_skip_dir(&name_lower, &skip_dirs)
+                        || ignore_rules.is_extra_ignored(&path_buf, true)
+                    {
                         skip.insert(r.file_ref);
                     } else {
-                        path_buf.clear();
-                        path_buf.push_str(parent_trimmed);
-                        path_buf.push('\\');
-                        path_buf.push_str(&r.filename);
                         paths.insert(r.file_ref, Arc::new(path_buf.clone()));
                     }
                     queue.push_back(r.file_ref);
@@ -1657,8 +3403,7 @@ pub fn init_search_index_internal(drive: char) -> bool {
     }
 
     // 构建索引项（并行获取文件元数据）
-    log::info!("📝 {} 盘开始构建索引项...", drive);
-    let indexed_items: Vec<IndexedItem> = records
+    records
         .par_iter()
         .filter_map(|r| {
             if skip.contains(&r.file_ref) {
@@ -1674,11 +3419,24 @@ pub fn init_search_index_internal(drive: char) -> bool {
             path.push('\\');
             path.push_str(&r.filename);
 
-            // 获取真实的文件元数据
-            let (size, mtime) = if r.is_dir {
-                (0, 0.0)
+            if !r.is_dir && ignore_rules.is_extra_ignored(&path, false) {
+                return None;
+            }
+
+            // 获取真实的文件元数据；is_symlink/ctime/atime 复用同一次 GetFileAttributesExW，免费拿到。
+            // 目录也要查一次——junction/挂载点本身就是"目录"属性的重解析点，不能只看文件
+            let (size, mtime, ctime, atime, ctime_raw, atime_raw, is_symlink, attrs) = match get_file_info_fast(&path) {
+                Some(stat) => {
+                    let size = if r.is_dir { 0 } else { stat.size };
+                    (size, stat.mtime, stat.ctime, stat.atime, stat.ctime_raw, stat.atime_raw, stat.is_reparse_point, stat.attrs)
+                }
+                None => (0, 0.0, 0.0, 0.0, 0, 0, false, 0),
+            };
+
+            let link_target = if is_symlink {
+                follow_reparse_chain(&path)
             } else {
-                get_file_info_fast(&path).unwrap_or((0, 0.0))
+                None
             };
 
             Some(IndexedItem {
@@ -1690,22 +3448,18 @@ pub fn init_search_index_internal(drive: char) -> bool {
                 size,
                 is_dir: r.is_dir,
                 mtime,
+                extension: String::new(),  // 将在 build 中填充
+                file_type: search_index::FileType::classify(r.is_dir, is_symlink),
+                link_target,
+                hard_links: 1,
+                ctime,
+                atime,
+                ctime_raw,
+                atime_raw,
+                attrs,
             })
         })
-        .collect();
-
-    // 创建索引
-    log::info!("🏗️ {} 盘开始创建搜索索引：{} 个项目", drive, indexed_items.len());
-    let index = Arc::new(SearchIndex::new());
-    index.build(indexed_items);
-
-    // 尝试持久化（写到驱动器根目录）
-    log::info!("💾 {} 盘保存索引到磁盘...", drive);
-    let _ = index.save_to_file(Path::new(&index_path));
-
-    SEARCH_INDICES.write().insert(drive, index);
-    log::info!("✅ {} 盘索引构建完成！", drive);
-    true
+        .collect()
 }
 
 /// 强制重建搜索索引（删除旧文件并重新构建）
@@ -1721,6 +3475,7 @@ pub fn force_rebuild_search_index_internal(drive: char) -> bool {
             log::info!("✅ 已删除旧索引文件: {}", index_path);
         }
     }
+    let _ = std::fs::remove_file(index_meta_path(drive));
 
     // 清空内存索引缓存
     SEARCH_INDICES.write().remove(&drive);
@@ -1843,6 +3598,281 @@ pub extern "C" fn search_by_mtime_range(
     pack_search_results(results)
 }
 
+/// FFI: 按大小范围搜索（[min_size, max_size] 字节，闭区间）
+#[no_mangle]
+pub extern "C" fn search_by_size_range(
+    drive_letter: u16,
+    min_size: u64,
+    max_size: u64,
+    max_results: usize,
+) -> *mut SearchResultFFI {
+    let drive = (drive_letter as u8 as char).to_ascii_uppercase();
+
+    let indices = SEARCH_INDICES.read();
+    let index = match indices.get(&drive) {
+        Some(idx) => idx,
+        None => return std::ptr::null_mut(),
+    };
+
+    let results = index.search_by_size_range(min_size, max_size, max_results);
+    pack_search_results(results)
+}
+
+/// FFI: 按 Windows 文件属性位掩码搜索，外加一个可选的名称前缀（空指针/空串表示不限制前缀）。
+/// `include_mask`/`exclude_mask` 直接透传给 `SearchIndex::search_by_attributes`：一个文件命中
+/// 当且仅当 `(attrs & include_mask) == include_mask && (attrs & exclude_mask) == 0`，调用方按
+/// Win32 `FILE_ATTRIBUTE_*` 的位值自己拼 mask（如只读 0x1、隐藏 0x2、系统 0x4、存档 0x20）
+#[no_mangle]
+pub extern "C" fn search_by_attributes(
+    drive_letter: u16,
+    prefix_ptr: *const c_char,
+    include_mask: u32,
+    exclude_mask: u32,
+    max_results: usize,
+) -> *mut SearchResultFFI {
+    let drive = (drive_letter as u8 as char).to_ascii_uppercase();
+
+    let prefix = unsafe {
+        if prefix_ptr.is_null() {
+            ""
+        } else {
+            match CStr::from_ptr(prefix_ptr).to_str() {
+                Ok(s) => s,
+                Err(_) => return std::ptr::null_mut(),
+            }
+        }
+    };
+
+    let indices = SEARCH_INDICES.read();
+    let index = match indices.get(&drive) {
+        Some(idx) => idx,
+        None => return std::ptr::null_mut(),
+    };
+
+    let results = index.search_by_attributes(prefix, include_mask, exclude_mask, max_results);
+    pack_search_results(results)
+}
+
+/// FFI: 子树范围搜索，只返回 `folder_path` 指向的文件夹及其后代中匹配 `query` 的项（`query`
+/// 为空时返回整个子树）。薄封装 `SearchIndex::search_in_dir`——沿 `children_index` 的
+/// parent_ref 链路做 DFS，已经做了防环/深度上限处理，不会因为损坏的 MFT 数据死循环
+#[no_mangle]
+pub extern "C" fn search_in_folder(
+    drive_letter: u16,
+    folder_path_ptr: *const c_char,
+    query_ptr: *const c_char,
+    max_results: usize,
+) -> *mut SearchResultFFI {
+    let drive = (drive_letter as u8 as char).to_ascii_uppercase();
+
+    let (folder_path, query) = unsafe {
+        if folder_path_ptr.is_null() || query_ptr.is_null() {
+            return std::ptr::null_mut();
+        }
+        match (
+            CStr::from_ptr(folder_path_ptr).to_str(),
+            CStr::from_ptr(query_ptr).to_str(),
+        ) {
+            (Ok(f), Ok(q)) => (f, q),
+            _ => return std::ptr::null_mut(),
+        }
+    };
+
+    let indices = SEARCH_INDICES.read();
+    let index = match indices.get(&drive) {
+        Some(idx) => idx,
+        None => return std::ptr::null_mut(),
+    };
+
+    let results = index.search_in_dir(folder_path, query, max_results);
+    pack_search_results(results)
+}
+
+/// FFI: fzf 风格的子序列模糊搜索，按相关性排序返回，适合交互式键入场景。薄封装
+/// `SearchIndex::search_fuzzy_subsequence`——不要和按编辑距离做拼写容错的
+/// `SearchIndex::search_fuzzy`（供 `commands::search_fuzzy_files` 使用）混淆，两者是不同的匹配方式
+#[no_mangle]
+pub extern "C" fn search_fuzzy(
+    drive_letter: u16,
+    query_ptr: *const c_char,
+    max_results: usize,
+) -> *mut SearchResultFFI {
+    let drive = (drive_letter as u8 as char).to_ascii_uppercase();
+
+    let query = unsafe {
+        if query_ptr.is_null() {
+            return std::ptr::null_mut();
+        }
+        match CStr::from_ptr(query_ptr).to_str() {
+            Ok(s) => s,
+            Err(_) => return std::ptr::null_mut(),
+        }
+    };
+
+    let indices = SEARCH_INDICES.read();
+    let index = match indices.get(&drive) {
+        Some(idx) => idx,
+        None => return std::ptr::null_mut(),
+    };
+
+    let results = index.search_fuzzy_subsequence(query, max_results);
+    pack_search_results(results)
+}
+
+/// FFI: 单次索引扫描里同时评估名称/扩展名/大小范围/修改时间范围/目录与否/属性掩码这一整套
+/// 过滤条件，避免调用方为每个条件单独发一次 FFI 往返再自己做交集。薄封装
+/// `SearchIndex::search_filtered`，只是把 `FilterQuery` 换成一个 repr(C) 的 `SearchQueryFFI`
+#[no_mangle]
+pub extern "C" fn search_query(
+    drive_letter: u16,
+    query: *const SearchQueryFFI,
+    max_results: usize,
+) -> *mut SearchResultFFI {
+    let drive = (drive_letter as u8 as char).to_ascii_uppercase();
+
+    if query.is_null() {
+        return std::ptr::null_mut();
+    }
+    let query = unsafe { &*query };
+
+    let name_contains = unsafe {
+        if query.name_contains_ptr.is_null() {
+            None
+        } else {
+            match CStr::from_ptr(query.name_contains_ptr).to_str() {
+                Ok(s) => Some(s.to_string()),
+                Err(_) => return std::ptr::null_mut(),
+            }
+        }
+    };
+    let extensions = unsafe {
+        if query.ext_filter_ptr.is_null() {
+            Vec::new()
+        } else {
+            match CStr::from_ptr(query.ext_filter_ptr).to_str() {
+                Ok(s) => s
+                    .split(',')
+                    .map(|e| e.trim().to_string())
+                    .filter(|e| !e.is_empty())
+                    .collect(),
+                Err(_) => return std::ptr::null_mut(),
+            }
+        }
+    };
+
+    let filter = search_index::FilterQuery {
+        name_contains,
+        extensions,
+        size_min: if query.has_size_min != 0 { Some(query.size_min) } else { None },
+        size_max: if query.has_size_max != 0 { Some(query.size_max) } else { None },
+        mtime_min: if query.has_mtime_min != 0 { Some(query.mtime_min) } else { None },
+        mtime_max: if query.has_mtime_max != 0 { Some(query.mtime_max) } else { None },
+        file_type: None,
+        is_dir: match query.is_dir {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        },
+        attrs_include: query.attrs_include,
+        attrs_exclude: query.attrs_exclude,
+    };
+
+    let indices = SEARCH_INDICES.read();
+    let index = match indices.get(&drive) {
+        Some(idx) => idx,
+        None => return std::ptr::null_mut(),
+    };
+
+    let results = index.search_filtered(&filter, max_results);
+    pack_search_results(results)
+}
+
+/// 内容 grep 候选文件的体积上限：超过这个数直接跳过，避免单个大文件拖慢整批并行扫描
+const CONTENT_GREP_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// 二进制嗅探只看开头这么多字节，和 `grep`/git 判断 binary 的思路一样：采样里见到 NUL 就当二进制
+const CONTENT_GREP_SNIFF_LEN: usize = 8192;
+
+fn looks_binary(buf: &[u8]) -> bool {
+    buf.iter().take(CONTENT_GREP_SNIFF_LEN).any(|&b| b == 0)
+}
+
+/// 读一个候选文件、嗅探二进制/体积上限，命中 `query`（字面量）就返回第一处命中的字节偏移
+fn grep_file_for_literal(path: &str, query: &str) -> Option<i64> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > CONTENT_GREP_MAX_FILE_SIZE {
+        return None;
+    }
+
+    let bytes = std::fs::read(path).ok()?;
+    if looks_binary(&bytes) {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&bytes);
+    text.find(query).map(|pos| pos as i64)
+}
+
+/// FFI: 内容（全文）grep 搜索。先用内存里的文件名索引按扩展名圈定候选集（没给扩展名就退化成
+/// 全量遍历），避免打开整盘文件去逐个扫描；候选集再并行（rayon 全局线程池，天然是个有限大小的
+/// 线程池）逐个读取，跳过体积超限或嗅探出来的二进制文件，对文本内容做一次字面量查找。
+/// 和 `content_index::search_content`（只覆盖 USN 增量管道已经分词过的白名单扩展名，靠倒排索引
+/// 秒回）是两条不同的路：这里现读现扫，覆盖面不受扩展名白名单限制，但没有预建索引加速，开销
+/// 完全靠候选集预筛来控制
+#[no_mangle]
+pub extern "C" fn search_content(
+    drive_letter: u16,
+    query_ptr: *const c_char,
+    ext_filter_ptr: *const c_char,
+    max_results: usize,
+) -> *mut SearchResultFFI {
+    let drive = (drive_letter as u8 as char).to_ascii_uppercase();
+
+    let query = unsafe {
+        if query_ptr.is_null() {
+            return std::ptr::null_mut();
+        }
+        match CStr::from_ptr(query_ptr).to_str() {
+            Ok(s) if !s.is_empty() => s,
+            _ => return std::ptr::null_mut(),
+        }
+    };
+
+    let ext_filter = unsafe {
+        if ext_filter_ptr.is_null() {
+            None
+        } else {
+            CStr::from_ptr(ext_filter_ptr)
+                .to_str()
+                .ok()
+                .filter(|s| !s.is_empty())
+        }
+    };
+
+    let indices = SEARCH_INDICES.read();
+    let index = match indices.get(&drive) {
+        Some(idx) => idx,
+        None => return std::ptr::null_mut(),
+    };
+
+    // 候选集上限留足冗余：grep 阶段还会因为二进制/超限/未命中再筛掉一批，必须比 max_results 宽松得多
+    const CANDIDATE_CAP: usize = 20_000;
+    let candidates: Vec<IndexedItem> = match ext_filter {
+        Some(ext) => index.search_by_extension(ext, CANDIDATE_CAP),
+        None => index.all_items(),
+    };
+    drop(indices);
+
+    let mut hits: Vec<(IndexedItem, i64)> = candidates
+        .into_par_iter()
+        .filter(|item| !item.is_dir)
+        .filter_map(|item| grep_file_for_literal(&item.path, query).map(|offset| (item, offset)))
+        .collect();
+    hits.truncate(max_results);
+
+    pack_search_results_with_offsets(hits)
+}
+
 /// FFI: 增量添加文件
 #[no_mangle]
 pub extern "C" fn index_add_file(
@@ -1880,6 +3910,15 @@ pub extern "C" fn index_add_file(
             size,
             is_dir: is_dir != 0,
             mtime: 0.0,
+            extension: String::new(),  // 将在 add_file 中填充
+            file_type: search_index::FileType::classify(is_dir != 0, false),
+            link_target: None,
+            hard_links: 1,
+            ctime: 0.0,
+            atime: 0.0,
+            ctime_raw: 0,
+            atime_raw: 0,
+            attrs: 0,
         });
         1
     } else {
@@ -1913,6 +3952,7 @@ pub extern "C" fn save_search_index(drive_letter: u16) -> i32 {
     if let Some(index) = indices.get(&drive) {
         let index_path = format!("{}:\\.search_index.bin", drive);
         if index.save_to_file(Path::new(&index_path)).is_ok() {
+            save_index_meta(drive, get_volume_serial(drive), get_current_usn(drive as u16));
             1
         } else {
             0
@@ -1922,7 +3962,8 @@ pub extern "C" fn save_search_index(drive_letter: u16) -> i32 {
     }
 }
 
-/// FFI: 从磁盘加载索引
+/// FFI: 从磁盘加载索引。索引文件损坏（magic/版本/校验和不匹配等）时不再直接返回失败，
+/// 而是透明地回退到全盘重建，避免调用方拿到一个空索引却以为加载成功了
 #[no_mangle]
 pub extern "C" fn load_search_index(drive_letter: u16) -> i32 {
     let drive = (drive_letter as u8 as char).to_ascii_uppercase();
@@ -1933,12 +3974,16 @@ pub extern "C" fn load_search_index(drive_letter: u16) -> i32 {
     }
 
     let index = Arc::new(SearchIndex::new());
-    if index.load_from_file(Path::new(&index_path)).is_ok() {
-        let mut indices = SEARCH_INDICES.write();
-        indices.insert(drive, index);
-        1
-    } else {
-        0
+    match index.load_from_file(Path::new(&index_path)) {
+        Ok(_) => {
+            let mut indices = SEARCH_INDICES.write();
+            indices.insert(drive, index);
+            1
+        }
+        Err(e) => {
+            log::warn!("⚠️ 加载磁盘索引失败（{}），回退到全盘重建: {}", index_path, e);
+            force_rebuild_search_index_internal(drive) as i32
+        }
     }
 }
 
@@ -1976,6 +4021,12 @@ pub extern "C" fn free_search_result(result: *mut SearchResultFFI) {
 
 // 辅助函数：打包搜索结果
 fn pack_search_results(results: Vec<IndexedItem>) -> *mut SearchResultFFI {
+    pack_search_results_with_offsets(results.into_iter().map(|item| (item, -1i64)).collect())
+}
+
+/// `pack_search_results` 的内容 grep 变体：每个结果额外带一个命中字节偏移，写进
+/// `SearchItemFFI::first_match_offset`；其余打包逻辑完全一致
+fn pack_search_results_with_offsets(results: Vec<(IndexedItem, i64)>) -> *mut SearchResultFFI {
     let count = results.len();
     if count == 0 {
         return Box::into_raw(Box::new(SearchResultFFI {
@@ -1986,7 +4037,7 @@ fn pack_search_results(results: Vec<IndexedItem>) -> *mut SearchResultFFI {
 
     let mut items = Vec::with_capacity(count);
 
-    for item in results {
+    for (item, first_match_offset) in results {
         let name_bytes = item.name.into_bytes().into_boxed_slice();
         let name_len = name_bytes.len();
         let name_ptr = Box::into_raw(name_bytes) as *mut u8;
@@ -2003,6 +4054,11 @@ fn pack_search_results(results: Vec<IndexedItem>) -> *mut SearchResultFFI {
             size: item.size,
             is_dir: if item.is_dir { 1 } else { 0 },
             mtime: item.mtime,
+            ctime: item.ctime,
+            atime: item.atime,
+            ctime_raw: item.ctime_raw,
+            atime_raw: item.atime_raw,
+            first_match_offset,
         });
     }
 
@@ -2045,4 +4101,60 @@ mod tests {
         assert!(is_cad_path("tangent"));
         assert!(!is_cad_path("documents"));
     }
+
+    /// 按 ISO9660 固定布局拼出一条目录记录：长度字节 + 到 `file identifier` 为止的
+    /// 固定字段都填 0，只摆正测试关心的那几个偏移（extent LBA、data length、flags、
+    /// 文件名长度+文件名），再视需要补一个偶数长度的填充字节
+    fn build_iso_dir_record(extent_lba: u32, data_len: u32, is_dir: bool, name: &[u8]) -> Vec<u8> {
+        let len_fi = name.len();
+        let mut record_len = 33 + len_fi;
+        if len_fi % 2 == 0 {
+            record_len += 1;
+        }
+
+        let mut record = vec![0u8; record_len];
+        record[0] = record_len as u8;
+        record[2..6].copy_from_slice(&extent_lba.to_le_bytes());
+        record[10..14].copy_from_slice(&data_len.to_le_bytes());
+        record[25] = if is_dir { 0x02 } else { 0x00 };
+        record[32] = len_fi as u8;
+        record[33..33 + len_fi].copy_from_slice(name);
+        record
+    }
+
+    #[test]
+    fn test_parse_iso_directory_entries_valid_record() {
+        let record = build_iso_dir_record(100, 4096, false, b"FILE.TXT;1");
+        let entries = parse_iso_directory_entries(&record, false);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "FILE.TXT");
+        assert_eq!(entries[0].extent_lba, 100);
+        assert_eq!(entries[0].data_len, 4096);
+        assert!(!entries[0].is_dir);
+    }
+
+    #[test]
+    fn test_parse_iso_directory_entries_skips_dot_entries() {
+        let record = build_iso_dir_record(200, 2048, true, &[0x00]);
+        let entries = parse_iso_directory_entries(&record, false);
+        assert!(entries.is_empty(), "`.` 自引用项应当被跳过");
+    }
+
+    /// 回归测试：损坏/截断的镜像把记录长度字节写成一个小于 ISO9660 固定头部（34 字节）的值时，
+    /// `parse_iso_directory_entries` 曾经会不经检查直接索引 `record[2..6]`/`record[32]` 等
+    /// 固定偏移，导致 panic。现在应当跳过这条记录并继续解析，而不是崩溃
+    #[test]
+    fn test_parse_iso_directory_entries_truncated_record_does_not_panic() {
+        // 第一条记录声明了 2 字节长度（远小于 34 字节的最小合法记录），
+        // 后面跟一条正常记录，确认损坏记录被跳过后解析能继续往下走
+        let mut extent = vec![2u8, 0u8];
+        extent.extend(build_iso_dir_record(300, 8192, false, b"OK.TXT"));
+
+        let entries = parse_iso_directory_entries(&extent, false);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "OK.TXT");
+        assert_eq!(entries[0].extent_lba, 300);
+    }
 }
\ No newline at end of file
@@ -16,10 +16,26 @@ fn main() {
             commands::realtime_search,
             commands::build_index,
             commands::check_index_status,
+            commands::clear_index,
+            commands::find_duplicate_files,
+            commands::cancel_duplicate_scan,
+            commands::search_files_db,
+            commands::search_filtered_files,
+            commands::search_content,
+            commands::grep_content_search,
+            commands::search_terms,
+            commands::search_fuzzy_files,
+            commands::search_in_dir,
+            commands::run_batch_actions,
             commands::get_all_drives,
+            commands::get_drives_detail,
+            commands::browse_directory,
+            commands::get_file_metadata,
             commands::open_file,
             commands::locate_file,
             commands::delete_file,
+            commands::rename_file,
+            commands::create_directory,
             commands::copy_to_clipboard,
             commands::export_csv,
             commands::get_config,
@@ -31,20 +47,18 @@ fn main() {
         .setup(|app| {
             // 注册全局快捷键
             hotkey::register_hotkeys(app)?;
-            
+
+            // 👁️ 监听 U 盘等可移动设备的插入/拔出，保持常驻索引与实际挂载状态一致
+            file_scanner_engine::devicewatch::spawn_device_watch(app.handle());
+
             // 🚀 启动时预加载所有驱动器索引（常驻内存）
             std::thread::spawn(|| {
                 log::info!("🚀 启动索引预加载...");
-                
-                // 获取所有驱动器
-                let drives: Vec<char> = ('C'..='Z')
-                    .filter(|&letter| {
-                        let drive = format!("{}:\\", letter);
-                        std::path::Path::new(&drive).exists()
-                    })
-                    .collect();
-                
-                log::info!("📂 检测到 {} 个驱动器: {:?}", drives.len(), drives);
+
+                // 只预加载固定盘/可移动盘，跳过网络盘和光驱（慢且可能挂起）
+                let drives = commands::preload_drive_letters();
+
+                log::info!("📂 检测到 {} 个待索引驱动器: {:?}", drives.len(), drives);
                 
                 // 为每个驱动器加载索引
                 for drive in drives {
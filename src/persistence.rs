@@ -0,0 +1,121 @@
+// persistence.rs - SearchIndex 的后台去抖动持久化 worker
+// 调用方通过 PersistenceHandle::enqueue 把变更丢进队列而不是直接改索引，worker 线程串行
+// 应用这些变更，并把 save_to_file 合并到 debounce 周期上，避免高频增删阻塞调用方线程
+
+use crate::search_index::{IndexedItem, SearchIndex};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// 一条待应用到索引的增量变更
+pub enum IndexUpdate {
+    Add(IndexedItem),
+    RemoveByRef(u64),
+    RemoveByPath(String),
+    Rebuild(Vec<IndexedItem>),
+}
+
+enum WorkerMessage {
+    Update(IndexUpdate),
+    FlushNow(mpsc::Sender<()>),
+    Shutdown,
+}
+
+/// 后台持久化 worker 的句柄。Drop 时会通知 worker 落盘并 join 线程，
+/// 保证干净关闭不会丢掉还在队列里的变更
+pub struct PersistenceHandle {
+    sender: mpsc::Sender<WorkerMessage>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl PersistenceHandle {
+    /// 把一条变更丢进队列，worker 串行应用，不在调用方线程上阻塞
+    pub fn enqueue(&self, update: IndexUpdate) {
+        let _ = self.sender.send(WorkerMessage::Update(update));
+    }
+
+    /// 立即触发一次落盘并阻塞等待完成，用于退出前或用户手动保存等需要确定性的场景
+    pub fn flush_now(&self) {
+        let (ack_tx, ack_rx) = mpsc::channel();
+        if self.sender.send(WorkerMessage::FlushNow(ack_tx)).is_ok() {
+            let _ = ack_rx.recv();
+        }
+    }
+}
+
+impl Drop for PersistenceHandle {
+    fn drop(&mut self) {
+        let _ = self.sender.send(WorkerMessage::Shutdown);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// 启动后台持久化 worker 线程
+pub fn spawn(index: Arc<SearchIndex>, path: PathBuf, debounce: Duration) -> PersistenceHandle {
+    let (sender, receiver) = mpsc::channel::<WorkerMessage>();
+
+    let worker = thread::spawn(move || worker_loop(index, path, debounce, receiver));
+
+    PersistenceHandle { sender, worker: Some(worker) }
+}
+
+fn apply_update(index: &SearchIndex, update: IndexUpdate) {
+    match update {
+        IndexUpdate::Add(item) => index.add_file(item),
+        IndexUpdate::RemoveByRef(file_ref) => {
+            index.remove_file(file_ref);
+        }
+        IndexUpdate::RemoveByPath(path) => {
+            index.remove_file_by_path(&path);
+        }
+        IndexUpdate::Rebuild(items) => index.build(items),
+    }
+}
+
+/// worker 主循环：每条消息应用完后检查是否已到 debounce 周期，到了就顺带落盘；
+/// 队列空闲超过 debounce 时同样落盘一次（前提是 `dirty`），两条路径共用同一个节流窗口
+fn worker_loop(index: Arc<SearchIndex>, path: PathBuf, debounce: Duration, receiver: mpsc::Receiver<WorkerMessage>) {
+    let mut last_flush = Instant::now();
+
+    loop {
+        let wait = debounce.saturating_sub(last_flush.elapsed());
+
+        match receiver.recv_timeout(wait) {
+            Ok(WorkerMessage::Update(update)) => apply_update(&index, update),
+            Ok(WorkerMessage::FlushNow(ack)) => {
+                let _ = index.save_to_file(&path);
+                last_flush = Instant::now();
+                let _ = ack.send(());
+                continue;
+            }
+            Ok(WorkerMessage::Shutdown) => {
+                if index.is_dirty() {
+                    let _ = index.save_to_file(&path);
+                }
+                return;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if index.is_dirty() {
+                    let _ = index.save_to_file(&path);
+                }
+                last_flush = Instant::now();
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                if index.is_dirty() {
+                    let _ = index.save_to_file(&path);
+                }
+                return;
+            }
+        }
+
+        if index.is_dirty() && last_flush.elapsed() >= debounce {
+            let _ = index.save_to_file(&path);
+            last_flush = Instant::now();
+        }
+    }
+}
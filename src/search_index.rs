@@ -1,10 +1,63 @@
 // search_index.rs - 高性能搜索索引（Trie + 倒排索引 + 增量更新）
 
 use radix_trie::{Trie, TrieCommon};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
+use std::collections::BinaryHeap;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 文件类型判别，仿 POSIX mode 的文件种类语义。Windows 路径目前只会产生
+/// `File`/`Dir`/`Symlink` 三种；`CharDevice`/`BlockDevice`/`Fifo`/`Socket`
+/// 只由 Unix 专用的 `classify_unix` 产出，`classify` 本身不会产出它们。
+/// 仓库里没有在 Unix 上遍历目录树的扫描器，`classify_unix` 目前没有调用方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileType {
+    File,
+    Dir,
+    Symlink,
+    CharDevice,
+    BlockDevice,
+    Fifo,
+    Socket,
+}
+
+impl FileType {
+    /// symlink 优先于 is_dir：重解析点在 Windows 上也可能指向目录
+    pub fn classify(is_dir: bool, is_symlink: bool) -> Self {
+        if is_symlink {
+            FileType::Symlink
+        } else if is_dir {
+            FileType::Dir
+        } else {
+            FileType::File
+        }
+    }
+
+    /// Unix 专用分类：借助 `std::os::unix::fs::FileTypeExt` 识别字符/块设备、
+    /// 命名管道与 socket，这些在 `classify` 的 Windows 语义里不存在
+    #[cfg(unix)]
+    pub fn classify_unix(file_type: std::fs::FileType) -> Self {
+        use std::os::unix::fs::FileTypeExt;
+
+        if file_type.is_symlink() {
+            FileType::Symlink
+        } else if file_type.is_dir() {
+            FileType::Dir
+        } else if file_type.is_char_device() {
+            FileType::CharDevice
+        } else if file_type.is_block_device() {
+            FileType::BlockDevice
+        } else if file_type.is_fifo() {
+            FileType::Fifo
+        } else if file_type.is_socket() {
+            FileType::Socket
+        } else {
+            FileType::File
+        }
+    }
+}
 
 /// 搜索索引项
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +71,319 @@ pub struct IndexedItem {
     pub size: u64,
     pub is_dir: bool,
     pub mtime: f64,
+    #[serde(skip)]  // 可从 name 重建，无需序列化
+    pub extension: String,
+    pub file_type: FileType,
+    /// 重解析点（符号链接/挂载点）指向的目标路径；非重解析点或目标未解析出来时为 None
+    #[serde(default)]
+    pub link_target: Option<String>,
+    /// 硬链接计数；查不到真实值（如 MFT 快速路径不为每个文件单独开句柄查询）时取 1
+    #[serde(default = "default_hard_links")]
+    pub hard_links: u32,
+    /// 创建时间，Unix 秒（浮点数），查不到时为 0.0
+    #[serde(default)]
+    pub ctime: f64,
+    /// 最后访问时间，Unix 秒（浮点数），查不到时为 0.0
+    #[serde(default)]
+    pub atime: f64,
+    /// 创建时间原始 FILETIME tick（100ns），仅 Windows 路径会填；查不到时为 0
+    #[serde(default)]
+    pub ctime_raw: i64,
+    /// 最后访问时间原始 FILETIME tick（100ns），仅 Windows 路径会填；查不到时为 0
+    #[serde(default)]
+    pub atime_raw: i64,
+    /// Windows 文件属性位掩码（只读/隐藏/系统/存档等），来自 `GetFileAttributesExW`；
+    /// 查不到时为 0。新增字段会让 `.search_index.bin` 的格式版本号往后跳一格，见
+    /// `SearchIndex::save_to_file`/`load_from_file`
+    pub attrs: u32,
+}
+
+fn default_hard_links() -> u32 {
+    1
+}
+
+/// `.search_index.bin` 的格式版本号。新增 `attrs` 字段后从隐式的 1 跳到 2；引入
+/// magic/checksum 头部后跳到 3；以后 `IndexedItem` 的序列化形状再变就继续往后加
+const INDEX_FILE_FORMAT_VERSION: u8 = 3;
+
+/// 文件头魔数，用来快速识别"这根本不是一个索引文件"（比如半截的垃圾、被截断的写入），
+/// 和版本号不匹配（"是索引文件但格式对不上"）区分开
+const INDEX_FILE_MAGIC: &[u8; 4] = b"SIDX";
+
+/// 头部固定长度：magic(4) + version(1) + item_count(4, u32 LE) + blake3 checksum(32)
+const INDEX_FILE_HEADER_LEN: usize = 4 + 1 + 4 + 32;
+
+/// 把 `items` 写到 `path`，带 magic/版本/item 数/校验和头部；先写到同目录下的 `.tmp` 文件
+/// 并 `sync_all` 落盘，再 `rename` 覆盖正式路径，保证正式文件要么是旧的完整版本要么是新的
+/// 完整版本，不会出现崩溃/断电留下的半截文件
+fn write_index_file(path: &Path, items: &[IndexedItem]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let serialized = bincode::serialize(items)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    let checksum = blake3::hash(&serialized);
+
+    let mut buf = Vec::with_capacity(INDEX_FILE_HEADER_LEN + serialized.len());
+    buf.extend_from_slice(INDEX_FILE_MAGIC);
+    buf.push(INDEX_FILE_FORMAT_VERSION);
+    buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+    buf.extend_from_slice(checksum.as_bytes());
+    buf.extend_from_slice(&serialized);
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = std::path::PathBuf::from(tmp_path);
+
+    let mut file = std::fs::File::create(&tmp_path)?;
+    file.write_all(&buf)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// 从文件名中提取小写扩展名（不含点），与 `ext_index` 的 key 约定一致
+fn extract_extension(name: &str) -> String {
+    name.rfind('.')
+        .map(|pos| name[pos + 1..].to_lowercase())
+        .unwrap_or_default()
+}
+
+/// 把文件名切分成词项：按非字母数字分隔符（空格、`_`、`-`、`.` 等）以及 camelCase 边界断词，
+/// 每个词项统一转小写，供 `term_index` 建索引与 `search_terms` 查询共用同一套规则
+fn tokenize_name(name: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut prev_is_lower_or_digit = false;
+
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            if c.is_uppercase() && prev_is_lower_or_digit && !current.is_empty() {
+                terms.push(current.to_lowercase());
+                current = String::new();
+            }
+            current.push(c);
+            prev_is_lower_or_digit = c.is_lowercase() || c.is_numeric();
+        } else {
+            if !current.is_empty() {
+                terms.push(current.to_lowercase());
+                current = String::new();
+            }
+            prev_is_lower_or_digit = false;
+        }
+    }
+    if !current.is_empty() {
+        terms.push(current.to_lowercase());
+    }
+
+    terms
+}
+
+/// 在已排序的 `haystack[start..]` 里查找 `target`：先指数扩大步长越过明显不够的区间，
+/// 再在越界点附近二分收窄，均摊下来比对整个列表做二分查找更快——尤其是在 `haystack`
+/// 比调用方传入的候选序列长得多的时候。返回 `(是否命中, 下一次查找可以从的游标位置)`，
+/// 游标单调递增，调用方对一个升序候选序列连续探测时可以复用
+pub(crate) fn galloping_search(haystack: &[usize], start: usize, target: usize) -> (bool, usize) {
+    if start >= haystack.len() || haystack[start] >= target {
+        return match haystack.get(start..).map(|s| s.binary_search(&target)) {
+            Some(Ok(pos)) => (true, start + pos + 1),
+            Some(Err(pos)) => (false, start + pos),
+            None => (false, start),
+        };
+    }
+
+    let mut prev = start;
+    let mut step = 1usize;
+    let mut cur = start + step;
+    while cur < haystack.len() && haystack[cur] < target {
+        prev = cur;
+        step *= 2;
+        cur += step;
+    }
+    // 循环退出时 `cur` 要么越界，要么恰好落在第一个 `>= target` 的位置——后一种情况下
+    // `cur` 本身可能就是目标，必须包含进下面的二分区间，否则 `haystack[cur] == target`
+    // 时会被 `hi` 排除在外，错误地报告"没找到"
+    let hi = (cur + 1).min(haystack.len());
+
+    match haystack[prev..hi].binary_search(&target) {
+        Ok(pos) => (true, prev + pos + 1),
+        Err(pos) => (false, prev + pos),
+    }
+}
+
+/// 排序规则元组的前四项：精确匹配、词边界命中、匹配偏移、文件名长度。
+/// mtime 与目录优先这两项需要 `total_cmp`/非 `Ord` 比较，留给调用方继续 `then_with`
+fn rank_key(item: &IndexedItem, query_lower: &str) -> (bool, bool, usize, usize) {
+    let pos = item.name_lower.find(query_lower);
+    let exact = item.name_lower == query_lower;
+    let offset = pos.unwrap_or(usize::MAX);
+    let at_word_boundary = match pos {
+        Some(0) => true,
+        Some(p) => item.name_lower[..p]
+            .chars()
+            .next_back()
+            .map(|c| !c.is_alphanumeric())
+            .unwrap_or(true),
+        None => false,
+    };
+
+    (!exact, !at_word_boundary, offset, item.name.len())
+}
+
+/// 经典 DP 编辑距离矩阵的单行实现：只算到 `key` 与 `query` 的距离，一旦当前行的最小值
+/// 超过 `max_edits` 就提前返回 None——后续字符只会让距离继续增大，不可能再落回界内
+fn bounded_levenshtein(query: &[char], key: &str, max_edits: usize) -> Option<usize> {
+    let qlen = query.len();
+    let mut prev_row: Vec<usize> = (0..=qlen).collect();
+
+    for (i, kc) in key.chars().enumerate() {
+        let mut cur_row = vec![0usize; qlen + 1];
+        cur_row[0] = i + 1;
+        for j in 1..=qlen {
+            let cost = if query[j - 1] == kc { 0 } else { 1 };
+            cur_row[j] = (prev_row[j] + 1)
+                .min(cur_row[j - 1] + 1)
+                .min(prev_row[j - 1] + cost);
+        }
+        if *cur_row.iter().min().unwrap() > max_edits {
+            return None;
+        }
+        prev_row = cur_row;
+    }
+
+    let dist = prev_row[qlen];
+    if dist <= max_edits {
+        Some(dist)
+    } else {
+        None
+    }
+}
+
+/// 快速判断 `query` 是否是 `candidate` 的子序列（字符按顺序出现，不要求连续），
+/// 在跑完整的 DP 打分前先把明显不可能命中的候选过滤掉
+fn is_subsequence(query: &[char], candidate: &[char]) -> bool {
+    let mut qi = 0;
+    for &c in candidate {
+        if qi == query.len() {
+            break;
+        }
+        if c == query[qi] {
+            qi += 1;
+        }
+    }
+    qi == query.len()
+}
+
+/// 某个候选字符位置是否是"词边界"：字符串开头、路径分隔符/`_`/`-`/空格/`.` 之后，
+/// 或 camelCase 的大写字母处（与其前一个字符比较，因此需要原始大小写的 `candidate_chars`）
+fn fuzzy_boundary_bonus(candidate_chars: &[char], pos: usize) -> i32 {
+    const BONUS_BOUNDARY: i32 = 10;
+    const BONUS_CAMEL: i32 = 8;
+
+    if pos == 0 {
+        return BONUS_BOUNDARY;
+    }
+    let prev = candidate_chars[pos - 1];
+    if matches!(prev, '\\' | '/' | '_' | '-' | ' ' | '.') {
+        return BONUS_BOUNDARY;
+    }
+    if prev.is_lowercase() && candidate_chars[pos].is_uppercase() {
+        return BONUS_CAMEL;
+    }
+    0
+}
+
+/// fzf 风格的子序列模糊打分：对 `query` 在 `candidate` 里的每一种合法对齐方式做一次 DP，
+/// 取最高分。匹配字符记基础分，连续命中、边界命中额外加分；两次命中之间跳过的字符越多，
+/// 扣分越多（用"沿候选串向右传播时持续衰减"代替显式计算间隔长度，等价但不用双重循环）。
+/// 调用方已经用 [`is_subsequence`] 过滤过，这里不再检查 `query` 是否可能匹配
+fn fuzzy_subsequence_score(
+    query_lower: &[char],
+    candidate_chars: &[char],
+    candidate_lower: &[char],
+) -> i32 {
+    const SCORE_MATCH: i32 = 16;
+    const BONUS_CONSECUTIVE: i32 = 8;
+    const PENALTY_GAP_LEADING: i32 = 5;
+    const PENALTY_GAP: i32 = 3;
+    const NEG_INF: i32 = i32::MIN / 4;
+
+    let n = query_lower.len();
+    let m = candidate_lower.len();
+
+    // 第 1 行（i=1）：query 的第一个字符可以出现在任意位置，越靠后leading gap 罚分越重
+    let mut prev_score = vec![NEG_INF; m + 1];
+    let mut prev_run = vec![0i32; m + 1];
+    for j in 1..=m {
+        if candidate_lower[j - 1] != query_lower[0] {
+            continue;
+        }
+        let gap = (j - 1) as i32;
+        prev_score[j] = SCORE_MATCH + fuzzy_boundary_bonus(candidate_chars, j - 1)
+            - gap * PENALTY_GAP_LEADING;
+        prev_run[j] = 1;
+    }
+
+    for qi in 1..n {
+        // 非连续命中时走这条"衰减传播"的前缀最优值：每往右挪一格还没碰到新的命中，
+        // 就按 PENALTY_GAP 衰减一次，效果等同于按跳过字符数扣分
+        let mut prefix_best = vec![NEG_INF; m + 1];
+        for j in 1..=m {
+            prefix_best[j] = prev_score[j].max(prefix_best[j - 1] - PENALTY_GAP);
+        }
+
+        let mut cur_score = vec![NEG_INF; m + 1];
+        let mut cur_run = vec![0i32; m + 1];
+        for j in 1..=m {
+            if candidate_lower[j - 1] != query_lower[qi] {
+                continue;
+            }
+            let bonus = fuzzy_boundary_bonus(candidate_chars, j - 1);
+
+            let consecutive = if prev_score[j - 1] > NEG_INF {
+                Some(prev_score[j - 1] + SCORE_MATCH + bonus + BONUS_CONSECUTIVE)
+            } else {
+                None
+            };
+            let gapped = if prefix_best[j - 1] > NEG_INF {
+                Some(prefix_best[j - 1] + SCORE_MATCH + bonus)
+            } else {
+                None
+            };
+
+            cur_score[j] = match (consecutive, gapped) {
+                (Some(a), Some(b)) => a.max(b),
+                (Some(a), None) => a,
+                (None, Some(b)) => b,
+                (None, None) => continue,
+            };
+            cur_run[j] = if consecutive == Some(cur_score[j]) {
+                prev_run[j - 1] + 1
+            } else {
+                1
+            };
+        }
+
+        prev_score = cur_score;
+        prev_run = cur_run;
+    }
+
+    prev_score.into_iter().max().unwrap_or(NEG_INF)
+}
+
+/// 统一路径规整：正斜杠转反斜杠再转小写，`remove_file_by_path`/`search_in_dir` 共用
+fn normalize_path(path: &str) -> String {
+    path.replace('/', "\\").to_lowercase()
+}
+
+/// 判断已规整的 `path_lower` 是否以 `prefix`（已规整）为路径边界前缀：不仅要求字符串前缀
+/// 匹配，前缀后一个字符还必须是路径分隔符或到达字符串末尾，避免 `C:\logs` 误命中 `C:\logs_old\a.txt`
+fn path_has_prefix_boundary(path_lower: &str, prefix: &str) -> bool {
+    path_lower
+        .strip_prefix(prefix)
+        .map(|rest| rest.is_empty() || rest.starts_with('\\'))
+        .unwrap_or(false)
 }
 
 /// 搜索索引（支持前缀搜索、扩展名过滤、增量更新）
@@ -28,24 +394,49 @@ pub struct SearchIndex {
     /// 扩展名倒排索引
     ext_index: RwLock<FxHashMap<String, Vec<usize>>>,
 
+    /// 文件名分词倒排索引：词项 -> 命中的下标列表（按下标升序，与插入顺序一致），
+    /// 供 `search_terms` 做多词 AND 查询时交集用
+    term_index: RwLock<FxHashMap<String, Vec<usize>>>,
+
     /// 文件引用到索引位置的映射（用于增量更新）
     file_ref_map: RwLock<FxHashMap<u64, usize>>,
 
+    /// 目录树索引：parent_ref -> 子项下标列表，供 `search_in_dir` 顺着 parent_ref 链路
+    /// 递归枚举子树，避免对 `items` 做全表扫描
+    children_index: RwLock<FxHashMap<u64, Vec<usize>>>,
+
     /// 实际的索引项数据
     items: RwLock<Vec<IndexedItem>>,
 
     /// 脏标记（是否需要持久化）
     dirty: RwLock<bool>,
+
+    /// 内容版本号，每次 build/add_file/remove_file 都会递增；
+    /// 供重复文件扫描等昂贵的派生计算判断结果是否还能复用
+    version: AtomicU64,
+
+    /// 墓碑计数（name 为空的已删除项），用于判断是否需要触发 [`compact`](Self::compact)
+    deleted_count: AtomicU64,
 }
 
+/// 墓碑数超过总项数的这个比例时自动触发 compact，避免 `items`/trie 值列表在高频增删下无限膨胀
+const COMPACT_TOMBSTONE_RATIO: f64 = 0.25;
+
+/// `search_in_dir` 子树 DFS 的深度上限，防的是 parent_ref 数据损坏成环时无限展开
+const MAX_SUBTREE_DEPTH: u32 = 128;
+
 impl SearchIndex {
     pub fn new() -> Self {
         Self {
             name_trie: RwLock::new(Trie::new()),
             ext_index: RwLock::new(FxHashMap::default()),
+            term_index: RwLock::new(FxHashMap::default()),
             file_ref_map: RwLock::new(FxHashMap::default()),
+            children_index: RwLock::new(FxHashMap::default()),
             items: RwLock::new(Vec::new()),
             dirty: RwLock::new(false),
+            version: AtomicU64::new(0),
+            deleted_count: AtomicU64::new(0),
         }
     }
 
@@ -53,23 +444,28 @@ impl SearchIndex {
     pub fn build(&self, items: Vec<IndexedItem>) {
         let mut name_trie = self.name_trie.write();
         let mut ext_index = self.ext_index.write();
+        let mut term_index = self.term_index.write();
         let mut file_ref_map = self.file_ref_map.write();
+        let mut children_index = self.children_index.write();
         let mut items_guard = self.items.write();
 
         // 清空旧索引
         *name_trie = Trie::new();
         ext_index.clear();
+        term_index.clear();
         file_ref_map.clear();
+        children_index.clear();
         items_guard.clear();
 
         // 预分配
         items_guard.reserve(items.len());
 
         for (idx, mut item) in items.into_iter().enumerate() {
-            // 预计算小写文件名
+            // 预计算小写文件名与扩展名
             item.name_lower = item.name.to_lowercase();
+            item.extension = extract_extension(&item.name);
             let name_lower = &item.name_lower;
-            
+
             // 索引文件名（小写）
             name_trie
                 .get_mut(name_lower)
@@ -84,13 +480,27 @@ impl SearchIndex {
                 ext_index.entry(ext).or_insert_with(Vec::new).push(idx);
             }
 
+            // 分词建立词项倒排索引，供多词 AND 查询使用；去重避免同一文件名里重复词项让
+            // posting list 出现重复下标
+            let mut terms = tokenize_name(&item.name);
+            terms.sort_unstable();
+            terms.dedup();
+            for term in terms {
+                term_index.entry(term).or_insert_with(Vec::new).push(idx);
+            }
+
             // 索引文件引用
             file_ref_map.insert(item.file_ref, idx);
 
+            // 记录到父目录的子项列表，供目录子树枚举使用
+            children_index.entry(item.parent_ref).or_insert_with(Vec::new).push(idx);
+
             items_guard.push(item);
         }
 
         *self.dirty.write() = true;
+        self.version.fetch_add(1, Ordering::Relaxed);
+        self.deleted_count.store(0, Ordering::Relaxed);
     }
 
     /// 增量更新：添加文件
@@ -98,8 +508,9 @@ impl SearchIndex {
         let mut items_guard = self.items.write();
         let idx = items_guard.len();
 
-        // 预计算小写文件名
+        // 预计算小写文件名与扩展名
         item.name_lower = item.name.to_lowercase();
+        item.extension = extract_extension(&item.name);
         let name_lower = &item.name_lower;
 
         // 更新各个索引
@@ -120,10 +531,21 @@ impl SearchIndex {
                 .push(idx);
         }
 
+        let mut terms = tokenize_name(&item.name);
+        terms.sort_unstable();
+        terms.dedup();
+        let mut term_index = self.term_index.write();
+        for term in terms {
+            term_index.entry(term).or_insert_with(Vec::new).push(idx);
+        }
+        drop(term_index);
+
         self.file_ref_map.write().insert(item.file_ref, idx);
+        self.children_index.write().entry(item.parent_ref).or_insert_with(Vec::new).push(idx);
         items_guard.push(item);
 
         *self.dirty.write() = true;
+        self.version.fetch_add(1, Ordering::Relaxed);
     }
 
     /// 增量更新：删除文件（真正删除）
@@ -144,17 +566,20 @@ impl SearchIndex {
                 items[idx].size = 0;
             }
             drop(items);
-            
+
             *self.dirty.write() = true;
+            self.version.fetch_add(1, Ordering::Relaxed);
+            self.deleted_count.fetch_add(1, Ordering::Relaxed);
+            self.maybe_compact();
             return true;
         }
 
         false
     }
-    
+
     /// 通过路径删除文件（用于 delete_file 命令）
     pub fn remove_file_by_path(&self, path: &str) -> bool {
-        let path_normalized = path.replace('/', "\\").to_lowercase();
+        let path_normalized = normalize_path(path);
         let mut items = self.items.write();
         let mut file_ref_map = self.file_ref_map.write();
         
@@ -178,12 +603,143 @@ impl SearchIndex {
         
         if found {
             *self.dirty.write() = true;
+            self.version.fetch_add(1, Ordering::Relaxed);
+            self.deleted_count.fetch_add(1, Ordering::Relaxed);
+            self.maybe_compact();
         }
-        
+
         found
     }
 
-    /// 前缀搜索
+    /// 增量刷新：不做整表重扫，只对已索引的每一项重新探测一次 `(size, mtime)`，mtime 有变化
+    /// 才更新、探测不到（文件已被删除）才摘除。探测方式由调用方通过 `stat_fn` 注入——本模块
+    /// 不关心具体怎么拿到文件状态（Windows `GetFileAttributesExW` 还是别的），只负责按结果
+    /// 更新索引。主要给没有变更日志可用的卷（FAT32/exFAT/网络映射盘）在进程重启后续用持久化
+    /// 索引时当增量校正手段，避免每次启动都要整盘重新遍历
+    pub fn refresh_with<F>(&self, mut stat_fn: F) -> usize
+    where
+        F: FnMut(&str) -> Option<(u64, f64)>,
+    {
+        // 先拍一份快照再释放读锁，避免 stat 系统调用期间一直占着锁
+        let snapshot: Vec<(u64, String, f64)> = {
+            let items = self.items.read();
+            items
+                .iter()
+                .filter(|it| !it.name.is_empty())
+                .map(|it| (it.file_ref, it.path.clone(), it.mtime))
+                .collect()
+        };
+
+        let mut changed = 0usize;
+        let mut gone = Vec::new();
+        for (file_ref, path, old_mtime) in snapshot {
+            match stat_fn(&path) {
+                None => gone.push(file_ref),
+                Some((size, mtime)) if (mtime - old_mtime).abs() > f64::EPSILON => {
+                    if let Some(&idx) = self.file_ref_map.read().get(&file_ref) {
+                        if let Some(it) = self.items.write().get_mut(idx) {
+                            it.size = size;
+                            it.mtime = mtime;
+                            changed += 1;
+                        }
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        for file_ref in gone {
+            if self.remove_file(file_ref) {
+                changed += 1;
+            }
+        }
+
+        if changed > 0 {
+            *self.dirty.write() = true;
+            self.version.fetch_add(1, Ordering::Relaxed);
+        }
+        changed
+    }
+
+    /// 墓碑比例超过 [`COMPACT_TOMBSTONE_RATIO`] 时自动回收，供 `remove_file`/`remove_file_by_path` 调用
+    fn maybe_compact(&self) {
+        let total = self.items.read().len();
+        if total == 0 {
+            return;
+        }
+
+        let deleted = self.deleted_count.load(Ordering::Relaxed) as f64;
+        if deleted / total as f64 > COMPACT_TOMBSTONE_RATIO {
+            self.compact();
+        }
+    }
+
+    /// 回收墓碑：重建 `items`（丢弃 name 为空的已删除项），并把 `name_trie`/`ext_index`/
+    /// `term_index`/`file_ref_map`/`children_index` 里记录的旧下标按 old->new 映射表改写到
+    /// 新位置，找不到映射的旧下标（对应被丢弃的墓碑）直接从值列表中剔除。也作为持久化前的
+    /// 手动入口，避免落盘快照带着死重量
+    pub fn compact(&self) {
+        let mut name_trie = self.name_trie.write();
+        let mut ext_index = self.ext_index.write();
+        let mut term_index = self.term_index.write();
+        let mut file_ref_map = self.file_ref_map.write();
+        let mut children_index = self.children_index.write();
+        let mut items = self.items.write();
+
+        let mut new_items = Vec::with_capacity(items.len());
+        let mut remap: FxHashMap<usize, usize> = FxHashMap::default();
+        for (old_idx, item) in items.drain(..).enumerate() {
+            if item.name.is_empty() {
+                continue;
+            }
+            remap.insert(old_idx, new_items.len());
+            new_items.push(item);
+        }
+        *items = new_items;
+
+        let mut new_trie = Trie::new();
+        for (key, indices) in name_trie.iter() {
+            let remapped: Vec<usize> = indices.iter().filter_map(|i| remap.get(i).copied()).collect();
+            if !remapped.is_empty() {
+                new_trie.insert(key.clone(), remapped);
+            }
+        }
+        *name_trie = new_trie;
+
+        for indices in ext_index.values_mut() {
+            *indices = indices.iter().filter_map(|i| remap.get(i).copied()).collect();
+        }
+        ext_index.retain(|_, indices| !indices.is_empty());
+
+        for indices in term_index.values_mut() {
+            *indices = indices.iter().filter_map(|i| remap.get(i).copied()).collect();
+        }
+        term_index.retain(|_, indices| !indices.is_empty());
+
+        for idx in file_ref_map.values_mut() {
+            if let Some(&new_idx) = remap.get(idx) {
+                *idx = new_idx;
+            }
+        }
+
+        for indices in children_index.values_mut() {
+            *indices = indices.iter().filter_map(|i| remap.get(i).copied()).collect();
+        }
+        children_index.retain(|_, indices| !indices.is_empty());
+
+        drop(items);
+        drop(children_index);
+        drop(file_ref_map);
+        drop(term_index);
+        drop(ext_index);
+        drop(name_trie);
+
+        self.deleted_count.store(0, Ordering::Relaxed);
+        *self.dirty.write() = true;
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 前缀搜索，按 [`rank_results`] 分桶相关性排序后再截断到 `max_results`
     pub fn search_prefix(&self, prefix: &str, max_results: usize) -> Vec<IndexedItem> {
         let prefix_lower = prefix.to_lowercase();
         let name_trie = self.name_trie.read();
@@ -191,7 +747,7 @@ impl SearchIndex {
 
         let mut results = Vec::new();
 
-        // 使用前缀树查找
+        // 使用前缀树查找，先收集全部候选，排序在截断之前进行，否则最佳命中可能被扫描顺序提前挤掉
         if let Some(subtrie) = name_trie.get_raw_descendant(&prefix_lower) {
             for indices in subtrie.values() {
                 for &idx in indices {
@@ -199,18 +755,33 @@ impl SearchIndex {
                         // 过滤已删除的项（name为空）
                         if !item.name.is_empty() {
                             results.push(item.clone());
-                            if results.len() >= max_results {
-                                return results;
-                            }
                         }
                     }
                 }
             }
         }
 
+        drop(items);
+        drop(name_trie);
+
+        let mut results = self.rank_results(&prefix_lower, results);
+        results.truncate(max_results);
         results
     }
 
+    /// 按相关性对候选结果分桶排序：(1) 精确匹配 (2) 命中起点在词边界 (3) 命中偏移更靠前
+    /// (4) 文件名更短 (5) 修改时间更新 (6) 目录优先于文件。每条规则在前一条的平局范围内
+    /// 再细分，整体等价于对这个规则元组做字典序排序
+    fn rank_results(&self, query_lower: &str, mut items: Vec<IndexedItem>) -> Vec<IndexedItem> {
+        items.sort_by(|a, b| {
+            rank_key(a, query_lower)
+                .cmp(&rank_key(b, query_lower))
+                .then_with(|| b.mtime.total_cmp(&a.mtime))
+                .then_with(|| b.is_dir.cmp(&a.is_dir))
+        });
+        items
+    }
+
     /// 模糊搜索（包含匹配）- 优化版本
     pub fn search_contains(&self, pattern: &str, max_results: usize) -> Vec<IndexedItem> {
         use rayon::prelude::*;
@@ -228,12 +799,162 @@ impl SearchIndex {
             .cloned()
             .collect();
         
-        log::info!("过滤完成，匹配 {} 项，取前 {} 项", filtered.len(), max_results);
-        let result = filtered.into_iter().take(max_results).collect();
+        log::info!("过滤完成，匹配 {} 项，按相关性排序后取前 {} 项", filtered.len(), max_results);
+        let mut result = self.rank_results(&pattern_lower, filtered);
+        result.truncate(max_results);
         log::info!("search_contains 完成");
         result
     }
 
+    /// 拼写容错搜索：在 `max_edits` 次编辑距离内匹配 trie 中的文件名，按距离升序返回，
+    /// 距离相同时保留 trie 的自然遍历顺序。逐个 key 跑标准 DP 并在行最小值超界时提前中止，
+    /// 避免对每个候选词都算完整的编辑距离矩阵
+    pub fn search_fuzzy(&self, query: &str, max_edits: u8, max_results: usize) -> Vec<IndexedItem> {
+        let query_lower = query.to_lowercase();
+        let query_chars: Vec<char> = query_lower.chars().collect();
+        let max_edits = max_edits as usize;
+
+        let name_trie = self.name_trie.read();
+        let items = self.items.read();
+
+        let mut scored: Vec<(usize, IndexedItem)> = Vec::new();
+        for (key, indices) in name_trie.iter() {
+            let dist = match bounded_levenshtein(&query_chars, key, max_edits) {
+                Some(d) => d,
+                None => continue,
+            };
+            for &idx in indices {
+                if let Some(item) = items.get(idx) {
+                    if !item.name.is_empty() {
+                        scored.push((dist, item.clone()));
+                    }
+                }
+            }
+        }
+
+        scored.sort_by_key(|(dist, _)| *dist);
+        scored.truncate(max_results);
+        scored.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// fzf 风格的子序列模糊搜索：`query` 的字符只需按顺序出现在文件名里（不要求连续），
+    /// 不同于 [`Self::search_fuzzy`] 的编辑距离容错，这里是给交互式键入场景用的相关性排序。
+    /// 用一个容量为 `max_results` 的小顶堆维护当前最优的候选，不对全量命中结果排序；
+    /// 同分时路径更短的排前面
+    pub fn search_fuzzy_subsequence(&self, query: &str, max_results: usize) -> Vec<IndexedItem> {
+        let query_lower: Vec<char> = query.to_lowercase().chars().collect();
+        if query_lower.is_empty() || max_results == 0 {
+            return Vec::new();
+        }
+
+        struct Candidate {
+            score: i32,
+            path_len: usize,
+            item: IndexedItem,
+        }
+        impl PartialEq for Candidate {
+            fn eq(&self, other: &Self) -> bool {
+                self.score == other.score && self.path_len == other.path_len
+            }
+        }
+        impl Eq for Candidate {}
+        impl PartialOrd for Candidate {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Candidate {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                // 堆顶要始终是已入选候选里"最差"的一个，好在来了更优候选时直接弹掉它：
+                // 分数低的排更大（更差），分数相同时路径更长的排更大（更差）
+                other.score.cmp(&self.score).then(self.path_len.cmp(&other.path_len))
+            }
+        }
+
+        let items = self.items.read();
+        let mut heap: BinaryHeap<Candidate> = BinaryHeap::with_capacity(max_results + 1);
+
+        for item in items.iter() {
+            if item.name.is_empty() {
+                continue;
+            }
+            let candidate_lower: Vec<char> = item.name_lower.chars().collect();
+            if !is_subsequence(&query_lower, &candidate_lower) {
+                continue;
+            }
+            let candidate_chars: Vec<char> = item.name.chars().collect();
+            let score = fuzzy_subsequence_score(&query_lower, &candidate_chars, &candidate_lower);
+
+            let candidate = Candidate {
+                score,
+                path_len: item.path.len(),
+                item: item.clone(),
+            };
+            if heap.len() < max_results {
+                heap.push(candidate);
+            } else if let Some(worst) = heap.peek() {
+                if candidate < *worst {
+                    heap.pop();
+                    heap.push(candidate);
+                }
+            }
+        }
+
+        let mut result: Vec<Candidate> = heap.into_vec();
+        result.sort();
+        result.into_iter().map(|c| c.item).collect()
+    }
+
+    /// 多词 AND 搜索：把查询串分词后，在 `term_index` 里查每个词的 posting list 并求交集，
+    /// 命中条件是文件名包含全部查询词（顺序不限）。从最短的 posting list 开始，逐个用其余
+    /// 列表（已按下标升序）做 galloping 探测，避免对长列表做整表扫描
+    pub fn search_terms(&self, query: &str, max_results: usize) -> Vec<IndexedItem> {
+        let mut query_terms = tokenize_name(query);
+        query_terms.sort_unstable();
+        query_terms.dedup();
+        if query_terms.is_empty() {
+            return Vec::new();
+        }
+
+        let term_index = self.term_index.read();
+        let mut lists: Vec<&Vec<usize>> = Vec::with_capacity(query_terms.len());
+        for term in &query_terms {
+            match term_index.get(term) {
+                Some(list) => lists.push(list),
+                None => return Vec::new(), // 有词完全没有命中，交集必为空
+            }
+        }
+        lists.sort_by_key(|list| list.len());
+
+        // 从最短的列表出发依次和其余列表求交集；candidates 本身保持升序，
+        // 所以每个列表可以用一个单调递增的游标做 galloping 探测，不必每次都从头二分
+        let mut candidates: Vec<usize> = lists[0].clone();
+        for list in &lists[1..] {
+            if candidates.is_empty() {
+                break;
+            }
+            let mut cursor = 0usize;
+            candidates.retain(|&idx| {
+                let (found, next_cursor) = galloping_search(list, cursor, idx);
+                cursor = next_cursor;
+                found
+            });
+        }
+        drop(term_index);
+
+        let items = self.items.read();
+        let results: Vec<IndexedItem> = candidates
+            .into_iter()
+            .filter_map(|idx| items.get(idx).cloned())
+            .filter(|item| !item.name.is_empty())
+            .collect();
+        drop(items);
+
+        let mut results = self.rank_results(&query.to_lowercase(), results);
+        results.truncate(max_results);
+        results
+    }
+
     /// 按扩展名搜索
     pub fn search_by_extension(&self, ext: &str, max_results: usize) -> Vec<IndexedItem> {
         let ext_lower = ext.to_lowercase();
@@ -251,29 +972,158 @@ impl SearchIndex {
         }
     }
 
-    /// 持久化到文件
-    pub fn save_to_file(&self, path: &Path) -> std::io::Result<()> {
-        use std::io::Write;
+    /// 按 Windows 文件属性位掩码搜索，外加一个可选的名称前缀。匹配规则：
+    /// `(attrs & include_mask) == include_mask && (attrs & exclude_mask) == 0`，
+    /// 即 include_mask 里要求的位必须全部命中，exclude_mask 里任何一位命中就排除
+    pub fn search_by_attributes(
+        &self,
+        prefix: &str,
+        include_mask: u32,
+        exclude_mask: u32,
+        max_results: usize,
+    ) -> Vec<IndexedItem> {
+        let prefix_lower = prefix.to_lowercase();
+        let items = self.items.read();
 
+        let mut out = Vec::new();
+        for item in items.iter() {
+            if item.name.is_empty() {
+                continue;
+            }
+            if !prefix_lower.is_empty() && !item.name_lower.starts_with(&prefix_lower) {
+                continue;
+            }
+            if (item.attrs & include_mask) != include_mask || (item.attrs & exclude_mask) != 0 {
+                continue;
+            }
+            out.push(item.clone());
+            if out.len() >= max_results {
+                break;
+            }
+        }
+        out
+    }
+
+    /// 目录子树内搜索：只返回 `path` 落在 `dir_path` 子树下的命中项，`query` 为空时返回整个子树。
+    /// 优先定位 `dir_path` 对应的索引项，沿 `children_index` 的 parent_ref 链路递归枚举子树，
+    /// 避免扫描全部条目；如果 `dir_path` 本身不是一个被索引的条目（例如盘符根目录 `C:\`），
+    /// 退化为对 `items` 做一次路径前缀边界匹配的全表扫描。`visited`/`MAX_SUBTREE_DEPTH` 防的是
+    /// MFT 数据损坏导致 parent_ref 成环（如 A 的父是 B、B 的父又是 A）时 DFS 无限展开
+    pub fn search_in_dir(&self, dir_path: &str, query: &str, max_results: usize) -> Vec<IndexedItem> {
+        let dir_normalized = normalize_path(dir_path);
+        let query_lower = query.to_lowercase();
         let items = self.items.read();
-        let serialized = bincode::serialize(&*items)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
 
-        let mut file = std::fs::File::create(path)?;
-        file.write_all(&serialized)?;
+        let root_ref = items
+            .iter()
+            .find(|item| !item.name.is_empty() && normalize_path(&item.path) == dir_normalized)
+            .map(|item| item.file_ref);
+
+        let mut results = Vec::new();
+        if let Some(root_ref) = root_ref {
+            let children_index = self.children_index.read();
+            let mut visited: FxHashSet<u64> = FxHashSet::default();
+            visited.insert(root_ref);
+            let mut stack = vec![(root_ref, 0u32)];
+            while let Some((parent_ref, depth)) = stack.pop() {
+                if depth >= MAX_SUBTREE_DEPTH {
+                    continue;
+                }
+                if let Some(indices) = children_index.get(&parent_ref) {
+                    for &idx in indices {
+                        if let Some(item) = items.get(idx) {
+                            if item.name.is_empty() {
+                                continue;
+                            }
+                            if visited.insert(item.file_ref) {
+                                stack.push((item.file_ref, depth + 1));
+                            }
+                            if query_lower.is_empty() || item.name_lower.contains(&query_lower) {
+                                results.push(item.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        } else {
+            // 目录本身没有被索引（盘符根目录等），退化为路径前缀边界扫描
+            for item in items.iter() {
+                if item.name.is_empty() {
+                    continue;
+                }
+                if !path_has_prefix_boundary(&normalize_path(&item.path), &dir_normalized) {
+                    continue;
+                }
+                if query_lower.is_empty() || item.name_lower.contains(&query_lower) {
+                    results.push(item.clone());
+                }
+            }
+        }
+        drop(items);
+
+        let mut results = self.rank_results(&query_lower, results);
+        results.truncate(max_results);
+        results
+    }
+
+    /// 持久化到文件。先写到同目录下的 `.tmp` 临时文件并 `sync_all`，再 `rename` 覆盖正式路径，
+    /// 避免进程崩溃或磁盘写满导致正式文件被截断成半成品
+    pub fn save_to_file(&self, path: &Path) -> std::io::Result<()> {
+        // 落盘前先回收墓碑，避免已删除的空项占用快照体积
+        self.compact();
+
+        let items = self.items.read();
+        write_index_file(path, &items)?;
 
         *self.dirty.write() = false;
         Ok(())
     }
 
-    /// 从文件加载
+    /// 从文件加载。头部不完整/magic 不符/版本不符/校验和不符都视为"损坏，需要重建"，
+    /// 统一通过 `ErrorKind::InvalidData` 返回，由调用方（FFI 层）兜底触发重建
     pub fn load_from_file(&self, path: &Path) -> std::io::Result<()> {
         let file = std::fs::File::open(path)?;
         let mmap = unsafe { memmap2::Mmap::map(&file)? };
 
-        let items: Vec<IndexedItem> = bincode::deserialize(&mmap)
+        if mmap.len() < INDEX_FILE_HEADER_LEN {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "索引文件头部不完整，需要重建",
+            ));
+        }
+        if &mmap[0..4] != INDEX_FILE_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "索引文件 magic 不匹配，需要重建",
+            ));
+        }
+        if mmap[4] != INDEX_FILE_FORMAT_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "索引文件格式版本不匹配，需要重建",
+            ));
+        }
+        let item_count = u32::from_le_bytes(mmap[5..9].try_into().unwrap());
+        let checksum = &mmap[9..INDEX_FILE_HEADER_LEN];
+        let payload = &mmap[INDEX_FILE_HEADER_LEN..];
+
+        if blake3::hash(payload).as_bytes().as_slice() != checksum {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "索引文件校验和不匹配，需要重建",
+            ));
+        }
+
+        let items: Vec<IndexedItem> = bincode::deserialize(payload)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
 
+        if items.len() != item_count as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "索引文件 item 数量与头部不一致，需要重建",
+            ));
+        }
+
         self.build(items);
         *self.dirty.write() = false;
 
@@ -284,11 +1134,31 @@ impl SearchIndex {
         *self.dirty.read()
     }
 
+    /// 启动后台去抖动持久化 worker：返回的 handle 接收 `enqueue` 的增量变更，串行应用到
+    /// `self` 上，并按 `debounce` 周期合并落盘到 `path`，而不是每次变更都同步写盘
+    pub fn spawn_persistence(
+        self: &std::sync::Arc<Self>,
+        path: std::path::PathBuf,
+        debounce: std::time::Duration,
+    ) -> crate::persistence::PersistenceHandle {
+        crate::persistence::spawn(std::sync::Arc::clone(self), path, debounce)
+    }
+
     pub fn item_count(&self) -> usize {
         // 只统计未删除的项（name非空）
         self.items.read().iter().filter(|item| !item.name.is_empty()).count()
     }
 
+    /// 内容版本号，用于判断派生计算（如重复文件扫描）的缓存是否仍然有效
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
+    }
+
+    /// 获取所有未删除索引项的快照
+    pub fn all_items(&self) -> Vec<IndexedItem> {
+        self.items.read().iter().filter(|item| !item.name.is_empty()).cloned().collect()
+    }
+
     /// 修改时间范围搜索（返回修改时间在 [min_mtime, max_mtime] 之间的项，max_results 上限）
     pub fn search_by_mtime_range(
         &self,
@@ -309,6 +1179,110 @@ impl SearchIndex {
         }
         out
     }
+
+    /// 大小范围搜索（返回体积在 [min_size, max_size] 字节之间的项，max_results 上限），
+    /// 与 [`Self::search_by_mtime_range`] 是同一种全表扫描的写法
+    pub fn search_by_size_range(
+        &self,
+        min_size: u64,
+        max_size: u64,
+        max_results: usize,
+    ) -> Vec<IndexedItem> {
+        let items = self.items.read();
+        let mut out = Vec::with_capacity(max_results.min(1024));
+        for it in items.iter() {
+            if it.size >= min_size && it.size <= max_size {
+                out.push(it.clone());
+                if out.len() >= max_results {
+                    break;
+                }
+            }
+        }
+        out
+    }
+
+    /// 组合过滤查询：在名称匹配的基础上叠加扩展名集合、大小范围、修改时间窗口，
+    /// 支持"找出本周修改过的、体积超过 100MB 的大视频"这类多条件联合查询
+    pub fn search_filtered(&self, query: &FilterQuery, max_results: usize) -> Vec<IndexedItem> {
+        let name_contains_lower = query.name_contains.as_ref().map(|s| s.to_lowercase());
+        let items = self.items.read();
+
+        let mut out = Vec::new();
+        for item in items.iter() {
+            if item.name.is_empty() {
+                continue;
+            }
+            if let Some(pattern) = &name_contains_lower {
+                if !item.name_lower.contains(pattern.as_str()) {
+                    continue;
+                }
+            }
+            if !query.extensions.is_empty()
+                && !query.extensions.iter().any(|e| e.eq_ignore_ascii_case(&item.extension))
+            {
+                continue;
+            }
+            if let Some(min) = query.size_min {
+                if item.size < min {
+                    continue;
+                }
+            }
+            if let Some(max) = query.size_max {
+                if item.size > max {
+                    continue;
+                }
+            }
+            if let Some(min) = query.mtime_min {
+                if item.mtime < min {
+                    continue;
+                }
+            }
+            if let Some(max) = query.mtime_max {
+                if item.mtime > max {
+                    continue;
+                }
+            }
+            if let Some(file_type) = query.file_type {
+                if item.file_type != file_type {
+                    continue;
+                }
+            }
+            if let Some(want_dir) = query.is_dir {
+                if item.is_dir != want_dir {
+                    continue;
+                }
+            }
+            // include_mask/exclude_mask 为 0 时天然不产生任何限制（`attrs & 0 == 0` 恒成立），
+            // 与 `search_by_attributes` 的语义保持一致，不需要额外的 Option 包装
+            if query.attrs_include != 0 && (item.attrs & query.attrs_include) != query.attrs_include {
+                continue;
+            }
+            if query.attrs_exclude != 0 && (item.attrs & query.attrs_exclude) != 0 {
+                continue;
+            }
+
+            out.push(item.clone());
+            if out.len() >= max_results {
+                break;
+            }
+        }
+        out
+    }
+}
+
+/// `search_filtered` 的组合查询条件，每个字段为空/`None`（或掩码为 0）时不参与过滤
+#[derive(Debug, Clone, Default)]
+pub struct FilterQuery {
+    pub name_contains: Option<String>,
+    pub extensions: Vec<String>,
+    pub size_min: Option<u64>,
+    pub size_max: Option<u64>,
+    pub mtime_min: Option<f64>,
+    pub mtime_max: Option<f64>,
+    pub file_type: Option<FileType>,
+    pub is_dir: Option<bool>,
+    pub attrs_include: u32,
+    pub attrs_exclude: u32,
 }
 
 #[cfg(test)]
@@ -329,6 +1303,15 @@ mod tests {
                 size: 100,
                 is_dir: false,
                 mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
             },
             IndexedItem {
                 name: "testing.doc".to_string(),
@@ -339,6 +1322,15 @@ mod tests {
                 size: 200,
                 is_dir: false,
                 mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
             },
         ];
 
@@ -361,6 +1353,15 @@ mod tests {
             size: 100,
             is_dir: false,
             mtime: 0.0,
+            extension: String::new(),
+            file_type: FileType::File,
+            link_target: None,
+            hard_links: 1,
+            ctime: 0.0,
+            atime: 0.0,
+            ctime_raw: 0,
+            atime_raw: 0,
+            attrs: 0,
         }];
 
         index.build(items);
@@ -383,6 +1384,15 @@ mod tests {
                 size: 100,
                 is_dir: false,
                 mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
             },
             IndexedItem {
                 name: "file2.doc".to_string(),
@@ -393,6 +1403,15 @@ mod tests {
                 size: 200,
                 is_dir: false,
                 mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
             },
         ];
 
@@ -401,4 +1420,565 @@ mod tests {
         let results = index.search_by_extension("txt", 10);
         assert_eq!(results.len(), 1);
     }
+
+    #[test]
+    fn test_version_increments_on_mutation() {
+        let index = SearchIndex::new();
+        let v0 = index.version();
+
+        index.build(vec![IndexedItem {
+            name: "a.txt".to_string(),
+            name_lower: "a.txt".to_string(),
+            path: "C:\\a.txt".to_string(),
+            file_ref: 1,
+            parent_ref: 0,
+            size: 10,
+            is_dir: false,
+            mtime: 0.0,
+            extension: String::new(),
+            file_type: FileType::File,
+            link_target: None,
+            hard_links: 1,
+            ctime: 0.0,
+            atime: 0.0,
+            ctime_raw: 0,
+            atime_raw: 0,
+            attrs: 0,
+        }]);
+        let v1 = index.version();
+        assert!(v1 > v0);
+
+        index.add_file(IndexedItem {
+            name: "b.txt".to_string(),
+            name_lower: "b.txt".to_string(),
+            path: "C:\\b.txt".to_string(),
+            file_ref: 2,
+            parent_ref: 0,
+            size: 20,
+            is_dir: false,
+            mtime: 0.0,
+            extension: String::new(),
+            file_type: FileType::File,
+            link_target: None,
+            hard_links: 1,
+            ctime: 0.0,
+            atime: 0.0,
+            ctime_raw: 0,
+            atime_raw: 0,
+            attrs: 0,
+        });
+        let v2 = index.version();
+        assert!(v2 > v1);
+
+        assert_eq!(index.all_items().len(), 2);
+    }
+
+    #[test]
+    fn test_search_filtered_combines_conditions() {
+        let index = SearchIndex::new();
+
+        index.build(vec![
+            IndexedItem {
+                name: "movie.mp4".to_string(),
+                name_lower: String::new(),
+                path: "C:\\movie.mp4".to_string(),
+                file_ref: 1,
+                parent_ref: 0,
+                size: 200_000_000,
+                is_dir: false,
+                mtime: 1000.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "notes.txt".to_string(),
+                name_lower: String::new(),
+                path: "C:\\notes.txt".to_string(),
+                file_ref: 2,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 2000.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "clip.mp4".to_string(),
+                name_lower: String::new(),
+                path: "C:\\clip.mp4".to_string(),
+                file_ref: 3,
+                parent_ref: 0,
+                size: 5_000_000,
+                is_dir: false,
+                mtime: 3000.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+        ]);
+
+        let query = FilterQuery {
+            extensions: vec!["mp4".to_string()],
+            size_min: Some(100_000_000),
+            ..Default::default()
+        };
+        let results = index.search_filtered(&query, 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "movie.mp4");
+    }
+
+    #[test]
+    fn test_fuzzy_search_ranks_by_edit_distance() {
+        let index = SearchIndex::new();
+
+        index.build(vec![
+            IndexedItem {
+                name: "document.txt".to_string(),
+                name_lower: String::new(),
+                path: "C:\\document.txt".to_string(),
+                file_ref: 1,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "documents.txt".to_string(),
+                name_lower: String::new(),
+                path: "C:\\documents.txt".to_string(),
+                file_ref: 2,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "unrelated.txt".to_string(),
+                name_lower: String::new(),
+                path: "C:\\unrelated.txt".to_string(),
+                file_ref: 3,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+        ]);
+
+        let results = index.search_fuzzy("documnet.txt", 2, 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "document.txt");
+    }
+
+    #[test]
+    fn test_search_contains_ranks_exact_match_first() {
+        let index = SearchIndex::new();
+
+        index.build(vec![
+            IndexedItem {
+                name: "annual_report_draft.txt".to_string(),
+                name_lower: String::new(),
+                path: "C:\\annual_report_draft.txt".to_string(),
+                file_ref: 1,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "report.txt".to_string(),
+                name_lower: String::new(),
+                path: "C:\\report.txt".to_string(),
+                file_ref: 2,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+        ]);
+
+        let results = index.search_contains("report", 10);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "report.txt");
+    }
+
+    #[test]
+    fn test_compact_reclaims_tombstones_and_keeps_lookups_working() {
+        let index = SearchIndex::new();
+
+        index.build(vec![
+            IndexedItem {
+                name: "keep.txt".to_string(),
+                name_lower: String::new(),
+                path: "C:\\keep.txt".to_string(),
+                file_ref: 1,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "drop.txt".to_string(),
+                name_lower: String::new(),
+                path: "C:\\drop.txt".to_string(),
+                file_ref: 2,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+        ]);
+
+        assert!(index.remove_file(2));
+        assert_eq!(index.all_items().len(), 1);
+
+        index.compact();
+
+        // 压缩后 items 里不应再残留墓碑，且剩余条目的各项索引仍能正确定位
+        assert_eq!(index.all_items().len(), 1);
+        assert_eq!(index.search_prefix("keep", 10).len(), 1);
+        assert_eq!(index.search_by_extension("txt", 10).len(), 1);
+    }
+
+    #[test]
+    fn test_search_terms_matches_any_order_and_camel_case() {
+        let index = SearchIndex::new();
+
+        index.build(vec![
+            IndexedItem {
+                name: "FinalProjectReport.docx".to_string(),
+                name_lower: String::new(),
+                path: "C:\\FinalProjectReport.docx".to_string(),
+                file_ref: 1,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "unrelated.docx".to_string(),
+                name_lower: String::new(),
+                path: "C:\\unrelated.docx".to_string(),
+                file_ref: 2,
+                parent_ref: 0,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+        ]);
+
+        let results = index.search_terms("project final", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "FinalProjectReport.docx");
+    }
+
+    #[test]
+    fn test_search_in_dir_respects_subtree_and_path_boundary() {
+        let index = SearchIndex::new();
+
+        index.build(vec![
+            IndexedItem {
+                name: "logs".to_string(),
+                name_lower: String::new(),
+                path: "C:\\logs".to_string(),
+                file_ref: 1,
+                parent_ref: 0,
+                size: 0,
+                is_dir: true,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::Dir,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "a.log".to_string(),
+                name_lower: String::new(),
+                path: "C:\\logs\\a.log".to_string(),
+                file_ref: 2,
+                parent_ref: 1,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "sub".to_string(),
+                name_lower: String::new(),
+                path: "C:\\logs\\sub".to_string(),
+                file_ref: 3,
+                parent_ref: 1,
+                size: 0,
+                is_dir: true,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::Dir,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "b.log".to_string(),
+                name_lower: String::new(),
+                path: "C:\\logs\\sub\\b.log".to_string(),
+                file_ref: 4,
+                parent_ref: 3,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+            IndexedItem {
+                name: "c.log".to_string(),
+                name_lower: String::new(),
+                path: "C:\\logs_old\\c.log".to_string(),
+                file_ref: 5,
+                parent_ref: 6,
+                size: 10,
+                is_dir: false,
+                mtime: 0.0,
+                extension: String::new(),
+                file_type: FileType::File,
+                link_target: None,
+                hard_links: 1,
+                ctime: 0.0,
+                atime: 0.0,
+                ctime_raw: 0,
+                atime_raw: 0,
+                attrs: 0,
+            },
+        ]);
+
+        // 递归子树：logs 下直接子项和 sub 下的孙子项都应命中，logs_old 是兄弟目录不应命中
+        let results = index.search_in_dir("C:\\logs", "", 10);
+        let mut names: Vec<&str> = results.iter().map(|i| i.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["a.log", "b.log", "sub"]);
+
+        // 查询词过滤在子树内继续生效
+        let filtered = index.search_in_dir("C:\\logs", "b.log", 10);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].name, "b.log");
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let index = SearchIndex::new();
+        index.build(vec![IndexedItem {
+            name: "roundtrip.bin".to_string(),
+            name_lower: "roundtrip.bin".to_string(),
+            path: "C:\\roundtrip.bin".to_string(),
+            file_ref: 42,
+            parent_ref: 0,
+            size: 1234,
+            is_dir: false,
+            mtime: 0.0,
+            extension: "bin".to_string(),
+            file_type: FileType::File,
+            link_target: None,
+            hard_links: 1,
+            ctime: 0.0,
+            atime: 0.0,
+            ctime_raw: 0,
+            atime_raw: 0,
+            attrs: 0,
+        }]);
+
+        let path = std::env::temp_dir()
+            .join(format!("search_index_roundtrip_{}.bin", std::process::id()));
+        index.save_to_file(&path).expect("save_to_file 应当成功");
+
+        let loaded = SearchIndex::new();
+        loaded.load_from_file(&path).expect("load_from_file 应当能读回刚写的文件");
+        std::fs::remove_file(&path).ok();
+
+        let results = loaded.search_prefix("roundtrip", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_ref, 42);
+        assert_eq!(results[0].size, 1234);
+    }
+
+    /// 回归测试：`load_from_file` 必须把被篡改/损坏的校验和当成"需要重建"而不是静默
+    /// 接受脏数据——翻转 payload 里的一个字节，读回时应该报 `InvalidData` 错误
+    #[test]
+    fn test_load_from_file_rejects_checksum_mismatch() {
+        let index = SearchIndex::new();
+        index.build(vec![IndexedItem {
+            name: "a.txt".to_string(),
+            name_lower: "a.txt".to_string(),
+            path: "C:\\a.txt".to_string(),
+            file_ref: 1,
+            parent_ref: 0,
+            size: 1,
+            is_dir: false,
+            mtime: 0.0,
+            extension: "txt".to_string(),
+            file_type: FileType::File,
+            link_target: None,
+            hard_links: 1,
+            ctime: 0.0,
+            atime: 0.0,
+            ctime_raw: 0,
+            atime_raw: 0,
+            attrs: 0,
+        }]);
+
+        let path = std::env::temp_dir()
+            .join(format!("search_index_corrupt_{}.bin", std::process::id()));
+        index.save_to_file(&path).expect("save_to_file 应当成功");
+
+        let mut bytes = std::fs::read(&path).expect("应当能读回刚写的文件");
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF; // 翻转 payload 最后一个字节，让校验和对不上
+
+        std::fs::write(&path, &bytes).expect("应当能写回篡改后的文件");
+
+        let loaded = SearchIndex::new();
+        let err = loaded
+            .load_from_file(&path)
+            .expect_err("校验和不匹配时不应该成功加载");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    /// 回归测试：指数探测阶段退出时，`cur` 本身可能恰好等于 `target`——旧版本的
+    /// `hi = cur.min(len)` 会把 `cur` 这个下标排在二分区间之外，导致明明存在的元素
+    /// 被误报成"没找到"
+    #[test]
+    fn test_galloping_search_matches_at_doubling_boundary() {
+        // prev=0, cur 在第一轮探测后即落在下标 1（值恰好是 target），修复前的
+        // `hi=cur.min(len)` 会把下标 1 排除在二分区间外；游标约定是“命中位置之后
+        // 一位”，所以修复后应为命中下标 1 再 +1 = 2，而不是 1
+        assert_eq!(galloping_search(&[5, 8], 0, 8), (true, 2));
+
+        // 更长的 posting list，让指数探测跨过多轮翻倍后恰好落在目标下标上
+        let haystack: Vec<usize> = (0..20).collect();
+        assert_eq!(galloping_search(&haystack, 0, 3), (true, 4));
+    }
 }
@@ -1,21 +1,64 @@
 // 搜索语法解析器 - 支持 Everything 风格的增强语法
-use regex::Regex;
+use crate::search_index::FileType;
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// 文件名匹配方式：`name:` 走普通子串匹配；`regex:`/`glob:` 走 fd/ripgrep 风格的模式匹配，
+/// 三者互斥——后解析到的会覆盖前面的（`extract_name_matcher` 在 `extract_name` 之后跑）
+#[derive(Debug, Clone)]
+pub enum NameMatcher {
+    Substring(String),
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+impl Default for NameMatcher {
+    fn default() -> Self {
+        NameMatcher::Substring(String::new())
+    }
+}
+
+impl NameMatcher {
+    /// 空子串视为"不限制"，和原来 `!filters.name_pattern.is_empty()` 的判断保持一致
+    pub fn matches(&self, filename: &str) -> bool {
+        match self {
+            NameMatcher::Substring(pattern) => {
+                pattern.is_empty() || filename.to_lowercase().contains(&pattern.to_lowercase())
+            }
+            NameMatcher::Glob(matcher) => matcher.is_match(filename),
+            NameMatcher::Regex(re) => re.is_match(filename),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct SearchFilters {
     pub ext: Vec<String>,
     pub size_min: u64,
     pub size_max: u64,
+    /// 修改时间下界（晚于/新于），来自裸 `dm:value` 或 `dm:>value`
     pub date_after: Option<u64>,
+    /// 修改时间上界（早于/旧于），来自 `dm:<value` 或 `dmb:value`
+    pub date_before: Option<u64>,
     pub path: String,
     pub name_pattern: String,
+    /// `name:`/`regex:`/`glob:` 解析出的文件名匹配器，`match_item`/`match_filters` 统一走这个，
+    /// 不再单独判断 `name_pattern`
+    pub name_matcher: NameMatcher,
+    /// `type:dir`/`type:file`/`type:symlink` 等语法解析出的种类过滤，None 表示不限
+    pub kind: Option<FileType>,
+    /// `type:exe`：扩展名为 `.exe` 的可执行文件（Windows 下没有单独的可执行位，靠扩展名判断）
+    pub only_exe: bool,
+    /// `type:empty`：大小为 0 的文件
+    pub only_empty: bool,
 }
 
 pub struct SearchSyntaxParser;
 
 impl SearchSyntaxParser {
-    pub fn parse(query: &str) -> (String, SearchFilters) {
+    /// 解析增强语法。`regex:`/`glob:` 模式编译失败时返回 `Err`，不会静默退化成"什么都匹配"
+    pub fn parse(query: &str) -> Result<(String, SearchFilters), String> {
         let mut filters = SearchFilters::default();
         let mut text = query.to_string();
 
@@ -25,11 +68,13 @@ impl SearchSyntaxParser {
         text = Self::extract_date(&text, &mut filters);
         text = Self::extract_path(&text, &mut filters);
         text = Self::extract_name(&text, &mut filters);
+        text = Self::extract_name_matcher(&text, &mut filters)?;
+        text = Self::extract_kind(&text, &mut filters);
 
         // 清理多余空格
         let pure_keyword = text.split_whitespace().collect::<Vec<_>>().join(" ");
 
-        (pure_keyword, filters)
+        Ok((pure_keyword, filters))
     }
 
     fn extract_ext(text: &str, filters: &mut SearchFilters) -> String {
@@ -88,54 +133,77 @@ impl SearchSyntaxParser {
         (num * multiplier as f64) as u64
     }
 
+    /// `dm:value` / `dm:>value` 设定下界（晚于/新于 value），`dm:<value` / `dmb:value` 设定
+    /// 上界（早于/旧于 value），两者可以同时出现组成一个时间窗口。`dmb:` 先解析，避免被
+    /// 后面裸 `dm:` 的正则再吃一遍（两者前缀不同，本身也不会互相匹配）
     fn extract_date(text: &str, filters: &mut SearchFilters) -> String {
-        let re = Regex::new(r"(?i)dm:(\S+)").unwrap();
-        let result = re.replace_all(text, "");
+        let mut result = text.to_string();
 
-        if let Some(cap) = re.captures(text) {
-            if let Some(date_str) = cap.get(1) {
-                let ds = date_str.as_str().to_lowercase();
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
-
-                let secs_in_day = 86400u64;
-
-                filters.date_after = match ds.as_str() {
-                    "today" => Some(now - now % secs_in_day),
-                    "yesterday" => Some(now - secs_in_day - now % secs_in_day),
-                    "week" => Some(now - 7 * secs_in_day),
-                    "month" => Some(now - 30 * secs_in_day),
-                    "year" => {
-                        // 今年1月1日
-                        let year_start = now - (now % (365 * secs_in_day));
-                        Some(year_start)
-                    }
-                    _ => {
-                        // 解析相对时间：7d, 12h, 30m
-                        let rel_re = Regex::new(r"^(\d+)([dhm])$").unwrap();
-                        if let Some(rel_cap) = rel_re.captures(&ds) {
-                            if let (Some(num), Some(unit)) = (rel_cap.get(1), rel_cap.get(2)) {
-                                let n: u64 = num.as_str().parse().unwrap_or(0);
-                                match unit.as_str() {
-                                    "d" => Some(now - n * secs_in_day),
-                                    "h" => Some(now - n * 3600),
-                                    "m" => Some(now - n * 60),
-                                    _ => None,
-                                }
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    }
-                };
+        let re_dmb = Regex::new(r"(?i)dmb:(\S+)").unwrap();
+        if let Some(cap) = re_dmb.captures(&result) {
+            if let Some(m) = cap.get(1) {
+                filters.date_before = Self::parse_date_token(m.as_str());
             }
         }
+        result = re_dmb.replace_all(&result, "").to_string();
 
-        result.to_string()
+        let re_dm_before = Regex::new(r"(?i)dm:<(\S+)").unwrap();
+        if let Some(cap) = re_dm_before.captures(&result) {
+            if let Some(m) = cap.get(1) {
+                filters.date_before = Self::parse_date_token(m.as_str());
+            }
+        }
+        result = re_dm_before.replace_all(&result, "").to_string();
+
+        let re_dm_after = Regex::new(r"(?i)dm:>?(\S+)").unwrap();
+        if let Some(cap) = re_dm_after.captures(&result) {
+            if let Some(m) = cap.get(1) {
+                filters.date_after = Self::parse_date_token(m.as_str());
+            }
+        }
+        result = re_dm_after.replace_all(&result, "").to_string();
+
+        result
+    }
+
+    /// 把 `dm:`/`dmb:` 后面的值解析成 Unix 时间戳：`today`/`yesterday`/`week`/`month`/`year`
+    /// 固定关键词、`7d`/`12h`/`30m` 相对时间，或 `YYYY-MM-DD` 绝对日期
+    fn parse_date_token(raw: &str) -> Option<u64> {
+        let ds = raw.to_lowercase();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let secs_in_day = 86400u64;
+
+        match ds.as_str() {
+            "today" => Some(now - now % secs_in_day),
+            "yesterday" => Some(now - secs_in_day - now % secs_in_day),
+            "week" => Some(now - 7 * secs_in_day),
+            "month" => Some(now - 30 * secs_in_day),
+            "year" => Some(now - (now % (365 * secs_in_day))),
+            _ => {
+                let rel_re = Regex::new(r"^(\d+)([dhm])$").unwrap();
+                if let Some(rel_cap) = rel_re.captures(&ds) {
+                    let n: u64 = rel_cap.get(1).unwrap().as_str().parse().unwrap_or(0);
+                    return match rel_cap.get(2).unwrap().as_str() {
+                        "d" => Some(now - n * secs_in_day),
+                        "h" => Some(now - n * 3600),
+                        "m" => Some(now - n * 60),
+                        _ => None,
+                    };
+                }
+                Self::parse_absolute_date(&ds)
+            }
+        }
+    }
+
+    /// 解析 `YYYY-MM-DD` 为当天 00:00（UTC）对应的 Unix 时间戳
+    fn parse_absolute_date(s: &str) -> Option<u64> {
+        use chrono::NaiveDate;
+        let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+        let datetime = date.and_hms_opt(0, 0, 0)?;
+        Some(datetime.and_utc().timestamp().max(0) as u64)
     }
 
     fn extract_path(text: &str, filters: &mut SearchFilters) -> String {
@@ -162,6 +230,77 @@ impl SearchSyntaxParser {
         if let Some(cap) = re.captures(text) {
             if let Some(n) = cap.get(1) {
                 filters.name_pattern = n.as_str().to_string();
+                filters.name_matcher = NameMatcher::Substring(n.as_str().to_string());
+            }
+        }
+        re.replace_all(text, "").to_string()
+    }
+
+    /// `regex:pattern`/`glob:pattern`（可选双引号包住带空格的模式），覆盖 `extract_name`
+    /// 可能已经设好的子串匹配。编译失败直接报错，不吞掉整个查询当成无过滤条件
+    fn extract_name_matcher(text: &str, filters: &mut SearchFilters) -> Result<String, String> {
+        let re_regex_quoted = Regex::new(r#"(?i)regex:"([^"]+)""#).unwrap();
+        if let Some(cap) = re_regex_quoted.captures(text) {
+            let pattern = cap.get(1).unwrap().as_str();
+            filters.name_matcher = NameMatcher::Regex(Self::compile_regex(pattern)?);
+            return Ok(re_regex_quoted.replace_all(text, "").to_string());
+        }
+        let re_regex = Regex::new(r"(?i)regex:(\S+)").unwrap();
+        if let Some(cap) = re_regex.captures(text) {
+            let pattern = cap.get(1).unwrap().as_str();
+            filters.name_matcher = NameMatcher::Regex(Self::compile_regex(pattern)?);
+            return Ok(re_regex.replace_all(text, "").to_string());
+        }
+
+        let re_glob_quoted = Regex::new(r#"(?i)glob:"([^"]+)""#).unwrap();
+        if let Some(cap) = re_glob_quoted.captures(text) {
+            let pattern = cap.get(1).unwrap().as_str();
+            filters.name_matcher = NameMatcher::Glob(Self::compile_glob(pattern)?);
+            return Ok(re_glob_quoted.replace_all(text, "").to_string());
+        }
+        let re_glob = Regex::new(r"(?i)glob:(\S+)").unwrap();
+        if let Some(cap) = re_glob.captures(text) {
+            let pattern = cap.get(1).unwrap().as_str();
+            filters.name_matcher = NameMatcher::Glob(Self::compile_glob(pattern)?);
+            return Ok(re_glob.replace_all(text, "").to_string());
+        }
+
+        Ok(text.to_string())
+    }
+
+    fn compile_regex(pattern: &str) -> Result<Regex, String> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("regex 模式 `{}` 无效: {}", pattern, e))
+    }
+
+    fn compile_glob(pattern: &str) -> Result<GlobMatcher, String> {
+        GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(|g| g.compile_matcher())
+            .map_err(|e| format!("glob 模式 `{}` 无效: {}", pattern, e))
+    }
+
+    /// `type:exe`/`type:empty` 不对应 `FileType` 的任何一种种类，直接落到独立的
+    /// `only_exe`/`only_empty` 标志上，由 `match_item` 分别按扩展名/大小判断
+    fn extract_kind(text: &str, filters: &mut SearchFilters) -> String {
+        let re = Regex::new(r"(?i)type:(\S+)").unwrap();
+        if let Some(cap) = re.captures(text) {
+            if let Some(k) = cap.get(1) {
+                match k.as_str().to_lowercase().as_str() {
+                    "file" => filters.kind = Some(FileType::File),
+                    "dir" | "folder" | "directory" => filters.kind = Some(FileType::Dir),
+                    "symlink" | "link" => filters.kind = Some(FileType::Symlink),
+                    "chardevice" => filters.kind = Some(FileType::CharDevice),
+                    "blockdevice" => filters.kind = Some(FileType::BlockDevice),
+                    "fifo" => filters.kind = Some(FileType::Fifo),
+                    "socket" => filters.kind = Some(FileType::Socket),
+                    "exe" => filters.only_exe = true,
+                    "empty" => filters.only_empty = true,
+                    _ => {}
+                };
             }
         }
         re.replace_all(text, "").to_string()
@@ -201,6 +340,11 @@ impl SearchSyntaxParser {
                 return false;
             }
         }
+        if let Some(date_before) = filters.date_before {
+            if item.mtime >= date_before {
+                return false;
+            }
+        }
 
         // 路径过滤
         if !filters.path.is_empty() {
@@ -211,15 +355,34 @@ impl SearchSyntaxParser {
             }
         }
 
-        // 文件名模式过滤
-        if !filters.name_pattern.is_empty() {
-            let pattern = filters.name_pattern.to_lowercase();
-            let filename_lower = item.filename.to_lowercase();
-            if !filename_lower.contains(&pattern) {
+        // 文件名模式过滤（子串/glob/regex，见 `NameMatcher`）
+        if !filters.name_matcher.matches(&item.filename) {
+            return false;
+        }
+
+        // 文件种类过滤
+        if let Some(kind) = filters.kind {
+            if item.file_type != kind {
+                return false;
+            }
+        }
+
+        // type:exe —— 扩展名为 .exe 的文件（这个仓库只跑在 Windows 上，没有独立的可执行位可查）
+        if filters.only_exe {
+            let ext = std::path::Path::new(&item.filename)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("");
+            if item.is_dir || !ext.eq_ignore_ascii_case("exe") {
                 return false;
             }
         }
 
+        // type:empty —— 大小为 0 的文件
+        if filters.only_empty && (item.is_dir || item.size != 0) {
+            return false;
+        }
+
         true
     }
 }